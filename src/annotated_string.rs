@@ -0,0 +1,41 @@
+use crate::annotation_type::AnnotationType;
+use std::fmt;
+
+pub struct AnnotatedStringPart {
+    pub string: String,
+    pub annotation_type: Option<AnnotationType>,
+}
+
+#[derive(Default)]
+pub struct AnnotatedString {
+    parts: Vec<AnnotatedStringPart>,
+}
+
+impl AnnotatedString {
+    pub fn push_str(&mut self, string: &str, annotation_type: Option<AnnotationType>) {
+        if let Some(last) = self.parts.last_mut()
+            && last.annotation_type == annotation_type
+        {
+            last.string.push_str(string);
+            return;
+        }
+
+        self.parts.push(AnnotatedStringPart {
+            string: string.to_string(),
+            annotation_type,
+        });
+    }
+
+    pub fn parts(&self) -> &[AnnotatedStringPart] {
+        &self.parts
+    }
+}
+
+impl fmt::Display for AnnotatedString {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        for part in &self.parts {
+            write!(formatter, "{}", part.string)?;
+        }
+        Ok(())
+    }
+}
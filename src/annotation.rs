@@ -0,0 +1,15 @@
+use crate::annotation_type::AnnotationType;
+
+/// Marks the graphemes in `start..end` of a `Line` to be rendered with a special style.
+#[derive(Copy, Clone, Debug)]
+pub struct Annotation {
+    pub kind: AnnotationType,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Annotation {
+    pub const fn contains(&self, grapheme_index: usize) -> bool {
+        self.start <= grapheme_index && grapheme_index < self.end
+    }
+}
@@ -0,0 +1,11 @@
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AnnotationType {
+    Selection,
+    Match,
+    SelectedMatch,
+    Keyword,
+    Type,
+    String,
+    Comment,
+    Number,
+}
@@ -1,4 +1,8 @@
+use crate::clipboard::ClipboardProvider;
+use crate::config::Config;
+use crate::goto_target::GotoTarget;
 use clap::{Parser, Subcommand};
+use std::path::Path;
 
 #[derive(Debug, Parser)]
 #[command(
@@ -11,24 +15,124 @@ use clap::{Parser, Subcommand};
 pub struct Args {
     #[command(subcommand)]
     pub command: Command,
+
+    /// Where copied text is sent, `internal` keeps it inside the editor
+    #[arg(long, global = true, value_enum)]
+    pub clipboard: Option<ClipboardProvider>,
+
+    /// Color theme, either `dark`, `light` or the path of a TOML theme file
+    #[arg(long, global = true)]
+    pub theme: Option<String>,
+
+    /// Configuration file to use instead of `~/.config/ed/config.toml`
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<String>,
 }
 
 #[derive(Subcommand, Debug)]
 pub enum Command {
     /// Used to open the given files inside separate editor windows
     Open {
-        /// Name of files to be opened
+        /// Name of files to be opened, optionally followed by `:LINE` or `:LINE:COLUMN`.
+        /// A `+LINE` argument places the caret of the file that follows it
         #[arg(value_name = "FILE")]
         file_names: Vec<String>,
     },
 }
 
-pub fn parse_args() -> Vec<String> {
+pub struct FileArgument {
+    pub file_name: String,
+    pub target: Option<GotoTarget>,
+}
+
+impl FileArgument {
+    /// Splits a trailing `:LINE` or `:LINE:COLUMN` off `argument`, unless a file
+    /// with that exact name exists.
+    fn parse(argument: &str) -> Self {
+        let file = |file_name: &str, target| Self {
+            file_name: file_name.to_string(),
+            target,
+        };
+        if Path::new(argument).exists() {
+            return file(argument, None);
+        }
+
+        let Some((rest, last)) = Self::split_number(argument) else {
+            return file(argument, None);
+        };
+        match Self::split_number(rest) {
+            Some((file_name, line)) => file(
+                file_name,
+                Some(GotoTarget::Absolute {
+                    line,
+                    column: Some(last),
+                }),
+            ),
+            None => file(
+                rest,
+                Some(GotoTarget::Absolute {
+                    line: last,
+                    column: None,
+                }),
+            ),
+        }
+    }
+
+    fn split_number(argument: &str) -> Option<(&str, usize)> {
+        let (rest, number) = argument.rsplit_once(':')?;
+        let number = number.parse().ok()?;
+        (!rest.is_empty()).then_some((rest, number))
+    }
+}
+
+pub struct Options {
+    pub files: Vec<FileArgument>,
+    pub clipboard: Option<ClipboardProvider>,
+    pub theme: Option<String>,
+    pub config: Option<String>,
+}
+
+impl Options {
+    /// Settings given on the command line take precedence over the config file.
+    pub fn override_config(&self, config: &mut Config) {
+        if let Some(clipboard) = self.clipboard {
+            config.clipboard = clipboard;
+        }
+        if let Some(theme) = &self.theme {
+            config.theme.clone_from(theme);
+        }
+    }
+}
+
+pub fn parse_args() -> Options {
     let args = Args::parse();
-    match args.command {
-        Command::Open { file_names } => file_names
-            .into_iter()
-            .map(|f| f.trim().to_string())
-            .collect(),
+    let files = match args.command {
+        Command::Open { file_names } => {
+            let mut files = Vec::new();
+            let mut pending_line = None;
+            for argument in file_names.iter().map(|f| f.trim()) {
+                if let Some(line) = argument
+                    .strip_prefix('+')
+                    .and_then(|line| line.parse::<usize>().ok())
+                {
+                    pending_line = Some(GotoTarget::Absolute { line, column: None });
+                    continue;
+                }
+
+                let mut file = FileArgument::parse(argument);
+                if let Some(target) = pending_line.take() {
+                    file.target = Some(target);
+                }
+                files.push(file);
+            }
+            files
+        }
+    };
+
+    Options {
+        files,
+        clipboard: args.clipboard,
+        theme: args.theme,
+        config: args.config,
     }
 }
@@ -0,0 +1,190 @@
+use std::fs::{self, File, Metadata, OpenOptions};
+use std::io::{Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static TEMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Replaces the content of `path` with `bytes` without ever leaving a partially written
+/// file behind: the bytes go to a temporary file in the same directory, which is synced
+/// and renamed over the original. A symlink is followed so its target is replaced, and
+/// the permissions and, where allowed, the owner of the original file are kept. A file
+/// without write permission is refused, although the directory would allow replacing it.
+pub fn write_atomically(path: &Path, bytes: &[u8]) -> Result<(), Error> {
+    write(path, bytes, false)
+}
+
+/// Like [`write_atomically`], but the file is only readable by its owner whatever the
+/// permissions of an existing file, for files holding the text of others such as swap files.
+pub fn write_private(path: &Path, bytes: &[u8]) -> Result<(), Error> {
+    write(path, bytes, true)
+}
+
+fn write(path: &Path, bytes: &[u8], private: bool) -> Result<(), Error> {
+    let target = resolve_symlink(path);
+    let metadata = fs::metadata(&target).ok();
+    if let Some(metadata) = &metadata
+        && !is_writable(&target, metadata)
+    {
+        return Err(Error::new(
+            ErrorKind::PermissionDenied,
+            format!("{} is read-only", target.display()),
+        ));
+    }
+    let directory = match target.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let file_name = target.file_name().map_or_else(
+        || String::from("file"),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    let temp_path = directory.join(format!(
+        ".{file_name}.ed-tmp-{}-{}",
+        process::id(),
+        TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let result = write_temp_file(&temp_path, metadata.as_ref(), private, bytes).and_then(|()| {
+        fs::rename(&temp_path, &target).map_err(|error| {
+            with_context(&error, &format!("could not replace {}", target.display()))
+        })
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+        return result;
+    }
+
+    // Makes the rename itself durable, not every platform can open a directory.
+    if let Ok(directory) = File::open(&directory) {
+        let _ = directory.sync_all();
+    }
+    Ok(())
+}
+
+/// A file without any write permission is read-only even for the superuser. Otherwise
+/// opening it for writing checks the permissions the way the file system does.
+fn is_writable(target: &Path, metadata: &Metadata) -> bool {
+    if metadata.permissions().readonly() {
+        return false;
+    }
+    match OpenOptions::new().write(true).open(target) {
+        Ok(_) => true,
+        Err(error) => error.kind() != ErrorKind::PermissionDenied,
+    }
+}
+
+fn write_temp_file(
+    temp_path: &Path,
+    target: Option<&Metadata>,
+    private: bool,
+    bytes: &[u8],
+) -> Result<(), Error> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        // The content is never readable by more users than the original allows.
+        let mode = if private {
+            0o600
+        } else {
+            target.map_or(0o666, |metadata| metadata.permissions().mode() & 0o777)
+        };
+        options.mode(mode);
+    }
+    let mut file = options.open(temp_path).map_err(|error| {
+        let directory = temp_path.parent().unwrap_or(temp_path);
+        with_context(
+            &error,
+            &format!(
+                "could not create a temporary file in {}",
+                directory.display()
+            ),
+        )
+    })?;
+    file.write_all(bytes).map_err(|error| {
+        with_context(&error, &format!("could not write {}", temp_path.display()))
+    })?;
+    file.sync_all().map_err(|error| {
+        with_context(
+            &error,
+            &format!("could not flush {} to disk", temp_path.display()),
+        )
+    })?;
+
+    if let Some(metadata) = target.filter(|_| !private) {
+        // The umask may have dropped some of the permissions the file was created with.
+        fs::set_permissions(temp_path, metadata.permissions())
+            .map_err(|error| with_context(&error, "could not copy the permissions of the file"))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{MetadataExt, chown};
+            // Only the superuser may give a file away, keeping our own ownership is fine.
+            let _ = chown(temp_path, Some(metadata.uid()), Some(metadata.gid()));
+        }
+    }
+    Ok(())
+}
+
+/// The file a symlink points to, so saving replaces the target and keeps the link.
+fn resolve_symlink(path: &Path) -> PathBuf {
+    let is_symlink = fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_symlink());
+    if !is_symlink {
+        return path.to_path_buf();
+    }
+    fs::canonicalize(path).unwrap_or_else(|_| {
+        // A dangling link, the target is created.
+        fs::read_link(path).map_or_else(
+            |_| path.to_path_buf(),
+            |link| path.parent().unwrap_or_else(|| Path::new("")).join(link),
+        )
+    })
+}
+
+fn with_context(error: &Error, context: &str) -> Error {
+    Error::new(error.kind(), format!("{context}: {error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("ed-{name}-{}", process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    #[test]
+    fn read_only_file_is_refused() {
+        let directory = temp_dir("read-only");
+        let path = directory.join("file.txt");
+        fs::write(&path, "old").unwrap();
+        let mut permissions = fs::metadata(&path).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&path, permissions).unwrap();
+
+        let error = write_atomically(&path, b"new").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::PermissionDenied);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+        assert_eq!(fs::read_dir(&directory).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn private_file_stays_private() {
+        use std::os::unix::fs::PermissionsExt;
+        let directory = temp_dir("private");
+        let path = directory.join("secret.txt");
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+
+        write_atomically(&path, b"new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
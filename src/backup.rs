@@ -0,0 +1,361 @@
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupMode {
+    /// Files are overwritten without a backup.
+    #[default]
+    Off,
+    /// `file~`, replaced on every save.
+    Single,
+    /// `file.~1~`, `file.~2~` and so on, one more on every save.
+    Numbered,
+    /// `file.~20261017-142501~`, named after the time of the save in UTC.
+    Timestamped,
+}
+
+/// Where and how the previous version of a file is kept when it is saved.
+#[derive(Clone, Debug, Default)]
+pub struct BackupPolicy {
+    mode: BackupMode,
+    /// Backups go next to the file when there is no backup directory.
+    directory: Option<PathBuf>,
+    /// How many numbered or timestamped backups of a file are kept, `0` keeps all.
+    keep: usize,
+}
+
+impl BackupPolicy {
+    pub fn new(mode: BackupMode, directory: &str, keep: usize) -> Self {
+        Self {
+            mode,
+            directory: (!directory.is_empty())
+                .then(|| expand_home(directory, env::var_os("HOME").as_deref().map(Path::new))),
+            keep,
+        }
+    }
+
+    /// Copies the file at `path` before it is overwritten. Nothing is done when backups
+    /// are off or the file does not exist yet.
+    pub fn back_up(&self, path: &Path) -> Result<(), Error> {
+        if self.mode == BackupMode::Off || !path.is_file() {
+            return Ok(());
+        }
+        let (directory, name) = self.location_of(path)?;
+        let backup = match self.mode {
+            BackupMode::Off => return Ok(()),
+            BackupMode::Single => directory.join(format!("{name}~")),
+            BackupMode::Numbered => {
+                let next = numbered_backups(&directory, &name)
+                    .last()
+                    .map_or(1, |(number, _)| number + 1);
+                directory.join(format!("{name}.~{next}~"))
+            }
+            // Saves within the same second get a `-1`, `-2`, … suffix.
+            BackupMode::Timestamped => {
+                let stamp = timestamp(SystemTime::now());
+                let mut backup = directory.join(format!("{name}.~{stamp}~"));
+                let mut collision = 0;
+                while backup.exists() {
+                    collision += 1;
+                    backup = directory.join(format!("{name}.~{stamp}-{collision}~"));
+                }
+                backup
+            }
+        };
+        fs::copy(path, &backup).map_err(|error| {
+            Error::new(
+                error.kind(),
+                format!("could not back up to {}: {error}", backup.display()),
+            )
+        })?;
+        self.remove_old_backups(&directory, &name);
+        Ok(())
+    }
+
+    /// The directory of the backups of `path` and the file name they start with. In a
+    /// backup directory the name is the whole path with `%` for `/`, so files with the
+    /// same name in different directories do not share their backups.
+    fn location_of(&self, path: &Path) -> Result<(PathBuf, String), Error> {
+        if let Some(directory) = &self.directory {
+            if !directory.is_absolute() {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "backup directory {} is not an absolute path",
+                        directory.display()
+                    ),
+                ));
+            }
+            fs::create_dir_all(directory).map_err(|error| {
+                Error::new(
+                    error.kind(),
+                    format!("could not create {}: {error}", directory.display()),
+                )
+            })?;
+            let absolute = fs::canonicalize(path)?;
+            let name = absolute.to_string_lossy().replace(['/', '\\'], "%");
+            return Ok((directory.clone(), name));
+        }
+        let name = path
+            .file_name()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "not a file"))?
+            .to_string_lossy()
+            .into_owned();
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        Ok((directory, name))
+    }
+
+    fn remove_old_backups(&self, directory: &Path, name: &str) {
+        if self.keep == 0 {
+            return;
+        }
+        let backups: Vec<PathBuf> = match self.mode {
+            BackupMode::Off | BackupMode::Single => return,
+            BackupMode::Numbered => numbered_backups(directory, name)
+                .into_iter()
+                .map(|(_, path)| path)
+                .collect(),
+            BackupMode::Timestamped => timestamped_backups(directory, name),
+        };
+        let excess = backups.len().saturating_sub(self.keep);
+        for backup in &backups[..excess] {
+            let _ = fs::remove_file(backup);
+        }
+    }
+}
+
+/// The backups `name.~tag~` in `directory` with their tag.
+fn backups_of(directory: &Path, name: &str) -> impl Iterator<Item = (String, PathBuf)> {
+    let prefix = format!("{name}.~");
+    fs::read_dir(directory)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter_map(move |entry| {
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let tag = file_name.strip_prefix(&prefix)?.strip_suffix('~')?;
+            Some((tag.to_string(), entry.path()))
+        })
+}
+
+/// The numbered backups of `name`, oldest first.
+fn numbered_backups(directory: &Path, name: &str) -> Vec<(usize, PathBuf)> {
+    let mut backups: Vec<(usize, PathBuf)> = backups_of(directory, name)
+        .filter_map(|(tag, path)| Some((tag.parse().ok()?, path)))
+        .collect();
+    backups.sort();
+    backups
+}
+
+/// The timestamped backups of `name`, oldest first.
+fn timestamped_backups(directory: &Path, name: &str) -> Vec<PathBuf> {
+    let mut backups: Vec<((String, usize), PathBuf)> = backups_of(directory, name)
+        .filter_map(|(tag, path)| {
+            let (stamp, collision) = timestamp_order(&tag)?;
+            Some(((stamp.to_string(), collision), path))
+        })
+        .collect();
+    backups.sort();
+    backups.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Splits a `YYYYMMDD-HHMMSS` tag with an optional `-N` collision suffix. The fixed
+/// width timestamps sort by time, the suffixes by number.
+fn timestamp_order(tag: &str) -> Option<(&str, usize)> {
+    let stamp = tag.get(..15)?;
+    let is_stamp = stamp.bytes().enumerate().all(|(index, byte)| {
+        if index == 8 {
+            byte == b'-'
+        } else {
+            byte.is_ascii_digit()
+        }
+    });
+    let collision = match &tag[15..] {
+        "" => 0,
+        suffix => suffix.strip_prefix('-')?.parse().ok()?,
+    };
+    is_stamp.then_some((stamp, collision))
+}
+
+/// `directory` with a leading `~` replaced by the home directory.
+fn expand_home(directory: &str, home: Option<&Path>) -> PathBuf {
+    match (directory.strip_prefix('~'), home) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            home.join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(directory),
+    }
+}
+
+/// `YYYYMMDD-HHMMSS` in UTC.
+fn timestamp(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let (days, seconds_of_day) = (seconds / 86_400, seconds % 86_400);
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{year:04}{month:02}{day:02}-{:02}{:02}{:02}",
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60
+    )
+}
+
+/// The date `days` after 1970-01-01 in the proleptic Gregorian calendar.
+const fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = era * 400 + year_of_era + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn fresh_directory(test: &str) -> PathBuf {
+        let directory = env::temp_dir().join(format!("ed-backup-{test}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    fn file_names(directory: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn days_are_converted_to_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(59), (1970, 3, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(20_743), (2026, 10, 17));
+        assert_eq!(civil_from_days(47_541), (2100, 3, 1));
+    }
+
+    #[test]
+    fn timestamp_is_utc() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_792_247_101);
+        assert_eq!(timestamp(time), "20261017-142501");
+        assert_eq!(timestamp(UNIX_EPOCH), "19700101-000000");
+    }
+
+    #[test]
+    fn home_is_expanded() {
+        let home = Some(Path::new("/home/me"));
+        assert_eq!(
+            expand_home("~/.local/state/ed", home),
+            PathBuf::from("/home/me/.local/state/ed")
+        );
+        assert_eq!(expand_home("~", home), PathBuf::from("/home/me"));
+        assert_eq!(
+            expand_home("~other/backups", home),
+            PathBuf::from("~other/backups")
+        );
+        assert_eq!(
+            expand_home("/var/backups", home),
+            PathBuf::from("/var/backups")
+        );
+        assert_eq!(expand_home("~/backups", None), PathBuf::from("~/backups"));
+    }
+
+    #[test]
+    fn relative_backup_directory_is_refused() {
+        let directory = fresh_directory("relative");
+        let file = directory.join("notes.txt");
+        fs::write(&file, "saved").unwrap();
+
+        let policy = BackupPolicy::new(BackupMode::Single, "backups", 0);
+        let error = policy.back_up(&file).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        assert!(!Path::new("backups").exists());
+    }
+
+    #[test]
+    fn saves_within_a_second_keep_both_backups() {
+        let directory = fresh_directory("same-second");
+        let file = directory.join("notes.txt");
+        fs::write(&file, "first").unwrap();
+        let policy = BackupPolicy::new(BackupMode::Timestamped, "", 0);
+
+        policy.back_up(&file).unwrap();
+        fs::write(&file, "second").unwrap();
+        policy.back_up(&file).unwrap();
+
+        let backups = timestamped_backups(&directory, "notes.txt");
+        assert_eq!(backups.len(), 2);
+        assert_eq!(fs::read_to_string(&backups[0]).unwrap(), "first");
+        assert_eq!(fs::read_to_string(&backups[1]).unwrap(), "second");
+    }
+
+    #[test]
+    fn oldest_numbered_backups_are_removed() {
+        let directory = fresh_directory("numbered");
+        for name in [
+            "notes.txt.~1~",
+            "notes.txt.~2~",
+            "notes.txt.~10~",
+            "other.~1~",
+        ] {
+            fs::write(directory.join(name), "").unwrap();
+        }
+        let policy = BackupPolicy::new(BackupMode::Numbered, "", 2);
+        policy.remove_old_backups(&directory, "notes.txt");
+
+        assert_eq!(
+            file_names(&directory),
+            ["notes.txt.~10~", "notes.txt.~2~", "other.~1~"]
+        );
+    }
+
+    #[test]
+    fn oldest_timestamped_backups_are_removed() {
+        let directory = fresh_directory("timestamped");
+        for name in [
+            "notes.txt.~20260101-000000~",
+            "notes.txt.~20260101-000000-2~",
+            "notes.txt.~20260101-000000-10~",
+            "notes.txt.~20251231-235959~",
+            "notes.txt.~latest~",
+        ] {
+            fs::write(directory.join(name), "").unwrap();
+        }
+        let policy = BackupPolicy::new(BackupMode::Timestamped, "", 2);
+        policy.remove_old_backups(&directory, "notes.txt");
+
+        assert_eq!(
+            file_names(&directory),
+            [
+                "notes.txt.~20260101-000000-10~",
+                "notes.txt.~20260101-000000-2~",
+                "notes.txt.~latest~",
+            ]
+        );
+    }
+}
@@ -1,15 +1,29 @@
+use crate::atomic_file::write_atomically;
+use crate::backup::BackupPolicy;
+use crate::diff::unified_diff;
+use crate::disk_state::{DiskChange, DiskState};
 use crate::file_info::FileInfo;
+use crate::history::{Change, History, end_of_text};
 use crate::line::Line;
+use crate::line_ending::LineEnding;
 use crate::location::Location;
-use std::fs::File;
-use std::io::Write;
-use std::{char, fs::read_to_string, io::Error};
+use crate::swap_file::{FoundSwap, SwapFile};
+use crate::text_encoding::TextEncoding;
+use regex::Regex;
+use std::cmp::min;
+use std::path::{Path, PathBuf};
+use std::{
+    char, fs,
+    io::{Error, ErrorKind},
+};
 
 #[derive(Default)]
 pub struct Buffer {
     pub file_info: FileInfo,
     pub lines: Vec<Line>,
     pub dirty: bool,
+    history: History,
+    swap: Option<SwapFile>,
 }
 
 impl Buffer {
@@ -17,20 +31,78 @@ impl Buffer {
         self.lines.is_empty()
     }
 
-    pub fn load(file_name: &str) -> Result<Self, Error> {
-        let file_content = read_to_string(file_name)?;
-        let mut lines = Vec::new();
-        for line in file_content.lines() {
-            lines.push(Line::from(line));
+    /// Loads `file_name`, detecting its encoding and using `fallback` for text that is
+    /// neither UTF-8 nor UTF-16.
+    pub fn load(file_name: &str, fallback: TextEncoding) -> Result<Self, Error> {
+        let bytes = fs::read(file_name)?;
+        let (encoding, bom_len) = TextEncoding::detect(&bytes, fallback);
+        Self::decode(file_name, &bytes, encoding, bom_len)
+    }
+
+    pub fn load_with_encoding(file_name: &str, encoding: TextEncoding) -> Result<Self, Error> {
+        let bytes = fs::read(file_name)?;
+        let bom_len = if bytes.starts_with(encoding.bom()) {
+            encoding.bom().len()
+        } else {
+            0
+        };
+        Self::decode(file_name, &bytes, encoding, bom_len)
+    }
+
+    fn decode(
+        file_name: &str,
+        bytes: &[u8],
+        encoding: TextEncoding,
+        bom_len: usize,
+    ) -> Result<Self, Error> {
+        let content = encoding.decode(&bytes[bom_len..])?;
+        let final_newline = content.is_empty() || content.ends_with('\n');
+
+        let mut raw_lines: Vec<&str> = content.split('\n').collect();
+        let line_ending = LineEnding::detect(&raw_lines);
+        if final_newline {
+            raw_lines.pop();
         }
+        let terminated = if final_newline {
+            raw_lines.len()
+        } else {
+            raw_lines.len().saturating_sub(1)
+        };
+        let lines = raw_lines
+            .iter()
+            .enumerate()
+            .map(|(index, line)| match line_ending {
+                LineEnding::Crlf if index < terminated => {
+                    Line::from(line.strip_suffix('\r').unwrap_or(line))
+                }
+                _ => Line::from(line),
+            })
+            .collect();
 
         Ok(Self {
             lines,
-            file_info: FileInfo::from(file_name),
-            dirty: true,
+            file_info: FileInfo {
+                encoding,
+                line_ending,
+                final_newline,
+                bom: bom_len > 0,
+                disk_state: Some(DiskState::new(Path::new(file_name), bytes)),
+                ..FileInfo::from(file_name)
+            },
+            dirty: false,
+            history: History::default(),
+            swap: Some(SwapFile::for_file(Path::new(file_name))),
         })
     }
 
+    /// A buffer without a file that holds `text`, e.g. to show a diff.
+    pub fn from_text(text: &str) -> Self {
+        Self {
+            lines: text.split('\n').map(Line::from).collect(),
+            ..Self::default()
+        }
+    }
+
     pub fn number_of_lines(&self) -> usize {
         self.lines.len()
     }
@@ -42,11 +114,17 @@ impl Buffer {
             return;
         }
 
+        let text = character.to_string();
         if at.line_index == self.lines.len() {
-            self.lines.push(Line::from(&character.to_string()));
+            self.lines.push(Line::from(&text));
+            self.history.record_typing(Change::InsertLine {
+                index: at.line_index,
+                text,
+            });
             self.dirty = true;
         } else if let Some(line) = self.lines.get_mut(at.line_index) {
             line.insert_char(character, at.grapheme_index);
+            self.history.record_typing(Change::Insert { at: *at, text });
             self.dirty = true;
         }
     }
@@ -56,22 +134,30 @@ impl Buffer {
             return;
         }
 
-        if at.grapheme_index >= self.lines[at.line_index].grapheme_count() {
-            if at.line_index < self.lines.len() - 1 {
-                let next_line = self.lines.remove(at.line_index + 1);
-                self.lines[at.line_index].append(&next_line);
-                self.dirty = true;
+        let end = if at.grapheme_index >= self.lines[at.line_index].grapheme_count() {
+            if at.line_index >= self.lines.len() - 1 {
+                return;
+            }
+            Location {
+                line_index: at.line_index.saturating_add(1),
+                grapheme_index: 0,
             }
         } else {
-            self.lines[at.line_index].delete(at.grapheme_index);
-            self.dirty = true;
-        }
+            Location {
+                line_index: at.line_index,
+                grapheme_index: at.grapheme_index.saturating_add(1),
+            }
+        };
+
+        let text = self.remove_text(*at, end);
+        self.history.record(Change::Delete { at: *at, text });
+        self.dirty = true;
     }
 
-    #[allow(dead_code)]
     pub fn delete_line(&mut self, at: usize) {
         if self.number_of_lines() > at {
-            self.lines.remove(at);
+            let text = self.lines.remove(at).to_string();
+            self.history.record(Change::RemoveLine { index: at, text });
             self.dirty = true;
         }
     }
@@ -79,52 +165,414 @@ impl Buffer {
     pub fn insert_newline(&mut self, at: &Location) {
         if at.line_index == self.number_of_lines() {
             self.lines.push(Line::default());
+            self.history.record(Change::InsertLine {
+                index: at.line_index,
+                text: String::new(),
+            });
             self.dirty = true;
-        } else if let Some(line) = self.lines.get_mut(at.line_index) {
-            let new = line.split(at.grapheme_index);
-            self.lines.insert(at.line_index.saturating_add(1), new);
+        } else if at.line_index < self.number_of_lines() {
+            let text = String::from("\n");
+            self.insert_text(*at, &text);
+            self.history.record(Change::Insert { at: *at, text });
+            self.dirty = true;
+        }
+    }
+
+    /// Inserts `text`, which may span several lines, and returns the location right after it.
+    pub fn insert_str(&mut self, at: Location, text: &str) -> Location {
+        if at.line_index > self.lines.len() || text.is_empty() {
+            return at;
+        }
+
+        self.history.begin_group();
+        if at.line_index == self.lines.len() {
+            self.lines.push(Line::default());
+            self.history.record(Change::InsertLine {
+                index: at.line_index,
+                text: String::new(),
+            });
+        }
+        self.insert_text(at, text);
+        self.history.record(Change::Insert {
+            at,
+            text: text.to_string(),
+        });
+        self.history.end_group();
+        self.dirty = true;
+        end_of_text(at, text)
+    }
+
+    /// Replaces the whole text as a single undoable step.
+    fn replace_text(&mut self, text: &str) {
+        self.history.begin_group();
+        self.delete_range(
+            Location::default(),
+            Location {
+                line_index: self.lines.len(),
+                grapheme_index: 0,
+            },
+        );
+        self.insert_str(Location::default(), text);
+        self.history.end_group();
+        self.dirty = true;
+    }
+
+    pub fn delete_range(&mut self, start: Location, end: Location) {
+        if start >= end || start.line_index >= self.lines.len() {
+            return;
+        }
+
+        let end = if end.line_index >= self.lines.len() {
+            let line_index = self.lines.len().saturating_sub(1);
+            Location {
+                line_index,
+                grapheme_index: self.lines[line_index].grapheme_count(),
+            }
+        } else {
+            end
+        };
+
+        let text = self.remove_text(start, end);
+        if !text.is_empty() {
+            self.history.record(Change::Delete { at: start, text });
             self.dirty = true;
         }
     }
 
+    pub fn text_in_range(&self, start: Location, end: Location) -> String {
+        self.lines
+            .iter()
+            .enumerate()
+            .take(end.line_index.saturating_add(1))
+            .skip(start.line_index)
+            .map(|(line_index, line)| {
+                let from = if line_index == start.line_index {
+                    start.grapheme_index
+                } else {
+                    0
+                };
+                let to = if line_index == end.line_index {
+                    end.grapheme_index
+                } else {
+                    line.grapheme_count()
+                };
+                line.get_substring(from..to)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    // === Undo / Redo === //
+
+    pub fn begin_undo_group(&mut self) {
+        self.history.begin_group();
+    }
+
+    pub fn end_undo_group(&mut self) {
+        self.history.end_group();
+    }
+
+    /// Reverts the last recorded step and returns where the caret should be placed.
+    pub fn undo(&mut self) -> Option<Location> {
+        let changes = self.history.pop_undo()?;
+        let mut caret = None;
+        for change in changes.iter().rev() {
+            caret = Some(self.apply(&change.invert()));
+        }
+        self.dirty = !self.history.is_saved();
+        caret
+    }
+
+    /// Re-applies the last undone step and returns where the caret should be placed.
+    pub fn redo(&mut self) -> Option<Location> {
+        let changes = self.history.pop_redo()?;
+        let mut caret = None;
+        for change in &changes {
+            caret = Some(self.apply(change));
+        }
+        self.dirty = !self.history.is_saved();
+        caret
+    }
+
+    fn apply(&mut self, change: &Change) -> Location {
+        match change {
+            Change::Insert { at, text } => {
+                self.insert_text(*at, text);
+                change.end()
+            }
+            Change::Delete { at, .. } => {
+                self.remove_text(*at, change.end());
+                *at
+            }
+            Change::InsertLine { index, text } => {
+                let index = min(*index, self.lines.len());
+                self.lines.insert(index, Line::from(text));
+                change.end()
+            }
+            Change::RemoveLine { index, .. } => {
+                if *index < self.lines.len() {
+                    self.lines.remove(*index);
+                }
+                change.start()
+            }
+        }
+    }
+
+    fn insert_text(&mut self, at: Location, text: &str) {
+        let Some(line) = self.lines.get_mut(at.line_index) else {
+            return;
+        };
+
+        let remainder = line.split(at.grapheme_index);
+        let mut parts = text.split('\n');
+        if let Some(first) = parts.next() {
+            line.append(&Line::from(first));
+        }
+
+        let mut index = at.line_index;
+        for part in parts {
+            index = index.saturating_add(1);
+            self.lines.insert(index, Line::from(part));
+        }
+        self.lines[index].append(&remainder);
+    }
+
+    fn remove_text(&mut self, start: Location, end: Location) -> String {
+        if end.line_index >= self.lines.len() || end.line_index < start.line_index {
+            return String::new();
+        }
+
+        let tail = self.lines[end.line_index].split(end.grapheme_index);
+        let mut removed: Vec<String> = self
+            .lines
+            .drain(start.line_index.saturating_add(1)..=end.line_index)
+            .map(|line| line.to_string())
+            .collect();
+
+        let first = self.lines[start.line_index].split(start.grapheme_index);
+        removed.insert(0, first.to_string());
+        self.lines[start.line_index].append(&tail);
+        removed.join("\n")
+    }
+
     // === Save === //
 
-    pub(crate) fn save_as(&mut self, file_name: &str) -> Result<(), Error> {
-        let file_info = FileInfo::from(file_name);
-        self.save_to_file(&file_info)?;
-        self.file_info = file_info;
+    pub(crate) fn save_as(&mut self, file_name: &str, backup: &BackupPolicy) -> Result<(), Error> {
+        let path = PathBuf::from(file_name);
+        self.write_to(&path, backup)?;
+        if let Some(swap) = &mut self.swap {
+            swap.remove();
+        }
+        self.swap = Some(SwapFile::for_file(&path));
+        self.file_info.path = Some(path);
         self.dirty = false;
+        self.history.mark_saved();
         Ok(())
     }
 
-    fn save_to_file(&self, file_info: &FileInfo) -> Result<(), Error> {
-        if let Some(file_path) = &file_info.get_path() {
-            let mut file = File::create(file_path)?;
-            for line in &self.lines {
-                writeln!(file, "{line}")?;
-            }
+    pub fn save(&mut self, backup: &BackupPolicy) -> Result<(), Error> {
+        if let Some(path) = self.file_info.path.clone() {
+            self.write_to(&path, backup)?;
+            self.dirty = false;
+            self.history.mark_saved();
+            self.remove_swap();
         }
         Ok(())
     }
 
-    pub fn save(&mut self) -> Result<(), Error> {
-        if let Some(name) = &self.file_info.path {
-            let mut path = File::create(name)?;
-            for line in &self.lines {
-                writeln!(path, "{line}")?;
+    fn write_to(&mut self, path: &Path, backup: &BackupPolicy) -> Result<(), Error> {
+        let bytes = self
+            .file_info
+            .encoding
+            .encode(&self.file_content(), self.file_info.bom)?;
+        backup.back_up(path)?;
+        write_atomically(path, &bytes)?;
+        self.file_info.disk_state = Some(DiskState::new(path, &bytes));
+        Ok(())
+    }
+
+    /// The text as it is written to disk, in the line ending and final newline
+    /// of the loaded file.
+    fn file_content(&self) -> String {
+        let line_ending = self.file_info.line_ending.as_str();
+        let text: Vec<String> = self.lines.iter().map(ToString::to_string).collect();
+        let mut content = text.join(line_ending);
+        if self.file_info.final_newline && !self.lines.is_empty() {
+            content.push_str(line_ending);
+        }
+        content
+    }
+
+    /// Writes the file in `encoding` from now on. UTF-16 files get a byte order mark
+    /// so they can be recognized when they are opened again. Fails without changing
+    /// anything when `encoding` can not represent the text.
+    pub fn set_encoding(&mut self, encoding: TextEncoding) -> Result<(), Error> {
+        let bom = encoding.is_utf16() || (self.file_info.bom && !encoding.bom().is_empty());
+        encoding.encode(&self.file_content(), bom)?;
+
+        self.file_info.bom = bom;
+        self.file_info.encoding = encoding;
+        self.dirty = true;
+        self.history.forget_saved();
+        Ok(())
+    }
+
+    /// Switches the file to `line_ending`. The carriage returns kept in the text of a
+    /// file with mixed line endings are removed as a single undoable step.
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        if self.file_info.line_ending == LineEnding::Mixed {
+            self.history.begin_group();
+            for line_index in 0..self.lines.len() {
+                let line = &self.lines[line_index];
+                if line.ends_with('\r') {
+                    let end = line.grapheme_count();
+                    self.delete_range(
+                        Location {
+                            line_index,
+                            grapheme_index: end.saturating_sub(1),
+                        },
+                        Location {
+                            line_index,
+                            grapheme_index: end,
+                        },
+                    );
+                }
             }
-            self.dirty = false;
+            self.history.end_group();
         }
+        self.file_info.line_ending = line_ending;
+        self.dirty = true;
+        self.history.forget_saved();
+    }
+
+    // === Changes On Disk === //
+
+    /// Checks whether another program changed or deleted the file since it was last
+    /// read or written. A deleted file is reported once, saving creates it again.
+    /// Reads the file again, decoding it as `encoding`. The text on disk replaces the
+    /// one of the buffer as a single undoable step, so the reload can be undone.
+    pub fn reload(&mut self, encoding: TextEncoding) -> Result<(), Error> {
+        let Some(path) = self.file_info.path.clone() else {
+            return Err(Error::new(ErrorKind::NotFound, "no file to reopen"));
+        };
+        let on_disk = Self::load_with_encoding(&path.to_string_lossy(), encoding)?;
+        let text = on_disk.text();
+        if text != self.text() {
+            self.replace_text(&text);
+        }
+        self.file_info = on_disk.file_info;
+        self.remove_swap();
+        self.history.mark_saved();
+        self.dirty = false;
         Ok(())
     }
 
-    // === Search === //
+    pub fn check_disk(&mut self) -> DiskChange {
+        let (Some(path), Some(state)) = (&self.file_info.path, self.file_info.disk_state) else {
+            return DiskChange::Unchanged;
+        };
+        let (change, state) = state.check(path);
+        self.file_info.disk_state = Some(state);
+        change
+    }
+
+    /// Keeps the text as it is, the current file on disk is not reported as changed again.
+    pub fn ignore_disk_change(&mut self) {
+        if let Some(path) = &self.file_info.path
+            && let Ok(bytes) = fs::read(path)
+        {
+            self.file_info.disk_state = Some(DiskState::new(path, &bytes));
+        }
+    }
+
+    /// A unified diff from the file on disk to the text.
+    pub fn disk_diff(&self) -> Result<Option<String>, Error> {
+        let Some(path) = &self.file_info.path else {
+            return Err(Error::new(ErrorKind::NotFound, "no file"));
+        };
+        let on_disk = Self::load_with_encoding(&path.to_string_lossy(), self.file_info.encoding)?;
+        let name = self.file_info.to_string();
+        Ok(unified_diff(
+            &format!("{name} (on disk)"),
+            &on_disk.text(),
+            &format!("{name} (unsaved)"),
+            &self.text(),
+        ))
+    }
+
+    // === Swap File === //
+
+    /// The text with `'\n'` between the lines, as kept in the swap file.
+    fn text(&self) -> String {
+        let text: Vec<String> = self.lines.iter().map(ToString::to_string).collect();
+        text.join("\n")
+    }
+
+    /// Writes the unsaved changes to the swap file, if there are any.
+    pub fn write_swap(&mut self) -> Result<(), Error> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let text = self.text();
+        self.swap.as_mut().map_or(Ok(()), |swap| swap.write(&text))
+    }
+
+    pub fn remove_swap(&mut self) {
+        if let Some(swap) = &mut self.swap {
+            swap.remove();
+        }
+    }
+
+    /// The swap file another session left behind for this file.
+    pub fn found_swap(&self) -> Option<&FoundSwap> {
+        self.swap.as_ref().and_then(SwapFile::found)
+    }
+
+    /// Where the unsaved changes of this buffer are swapped to.
+    pub fn swap_path(&self) -> Option<&Path> {
+        self.swap.as_ref().and_then(SwapFile::path)
+    }
+
+    fn found_swap_text(&self) -> Result<String, Error> {
+        self.swap.as_ref().map_or_else(
+            || Err(Error::new(ErrorKind::NotFound, "no swap file found")),
+            SwapFile::read,
+        )
+    }
+
+    /// A unified diff from the text to the one of the found swap file, `None` when
+    /// they are the same.
+    pub fn found_swap_diff(&self) -> Result<Option<String>, Error> {
+        let swap_text = self.found_swap_text()?;
+        let swap_name = self
+            .found_swap()
+            .map(|found| found.path.display().to_string())
+            .unwrap_or_default();
+        Ok(unified_diff(
+            &self.file_info.to_string(),
+            &self.text(),
+            &swap_name,
+            &swap_text,
+        ))
+    }
 
-    pub(crate) fn search_forward(&self, from: Location, query: &Line) -> Option<Location> {
-        if query.is_empty() {
-            return None;
+    /// Replaces the text with the one of the found swap file as a single undoable step.
+    pub fn recover_swap(&mut self) -> Result<(), Error> {
+        let text = self.found_swap_text()?;
+        self.replace_text(&text);
+        if let Some(swap) = &mut self.swap {
+            swap.adopt(&text);
         }
+        Ok(())
+    }
 
+    pub fn discard_swap(&mut self) -> Result<(), Error> {
+        self.swap.as_mut().map_or(Ok(()), SwapFile::discard)
+    }
+
+    // === Search === //
+
+    pub(crate) fn search_forward(&self, from: Location, pattern: &Regex) -> Option<Location> {
         let mut is_first = true;
         for (index, line) in self
             .lines
@@ -141,7 +589,7 @@ impl Buffer {
                 0
             };
 
-            if let Some(grapheme_index) = line.search_forward(from_grapheme_index, query) {
+            if let Some(grapheme_index) = line.search_forward(from_grapheme_index, pattern) {
                 return Some(Location {
                     grapheme_index,
                     line_index: index,
@@ -151,11 +599,28 @@ impl Buffer {
         None
     }
 
-    pub(crate) fn search_backwards(&self, from: Location, query: &Line) -> Option<Location> {
-        if query.is_empty() {
-            return None;
-        }
+    /// Identifies the current text, so results computed from it can be reused until an edit.
+    pub(crate) const fn revision(&self) -> u64 {
+        self.history.revision()
+    }
 
+    /// Returns where every match starts, in order.
+    pub(crate) fn match_starts(&self, pattern: &Regex) -> Vec<Location> {
+        self.lines
+            .iter()
+            .enumerate()
+            .flat_map(|(line_index, line)| {
+                line.find_all(pattern)
+                    .into_iter()
+                    .map(move |range| Location {
+                        line_index,
+                        grapheme_index: range.start,
+                    })
+            })
+            .collect()
+    }
+
+    pub(crate) fn search_backwards(&self, from: Location, pattern: &Regex) -> Option<Location> {
         let mut is_first = true;
         for (index, line) in self
             .lines
@@ -178,7 +643,7 @@ impl Buffer {
                 line.grapheme_count()
             };
 
-            if let Some(grapheme_index) = line.search_backward(from_grapheme_index, query) {
+            if let Some(grapheme_index) = line.search_backward(from_grapheme_index, pattern) {
                 return Some(Location {
                     grapheme_index,
                     line_index: index,
@@ -188,3 +653,99 @@ impl Buffer {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    /// Loads `content` from a file and returns what saving it again would write.
+    fn round_trip(name: &str, content: &str) -> String {
+        let path = env::temp_dir().join(format!("ed-buffer-{}-{name}", std::process::id()));
+        fs::write(&path, content).unwrap();
+        let buffer = Buffer::load(&path.to_string_lossy(), TextEncoding::DEFAULT_FALLBACK);
+        fs::remove_file(&path).unwrap();
+        let buffer = buffer.unwrap();
+        let bytes = buffer
+            .file_info
+            .encoding
+            .encode(&buffer.file_content(), buffer.file_info.bom)
+            .unwrap();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn line_endings_round_trip() {
+        for (name, content) in [
+            ("lf", "one\ntwo\n"),
+            ("crlf", "one\r\ntwo\r\n"),
+            ("mixed", "one\r\ntwo\nthree\r\n"),
+            ("unterminated", "one\r\ntwo"),
+            ("bom", "\u{feff}one\r\n"),
+            ("empty", ""),
+        ] {
+            assert_eq!(round_trip(name, content), content, "{name}");
+        }
+    }
+
+    #[test]
+    fn loaded_file_is_not_modified() {
+        let path = env::temp_dir().join(format!("ed-buffer-{}-clean", std::process::id()));
+        fs::write(&path, "one\n").unwrap();
+        let buffer = Buffer::load(&path.to_string_lossy(), TextEncoding::DEFAULT_FALLBACK).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(!buffer.dirty);
+    }
+
+    #[test]
+    fn unrepresentable_encoding_is_refused() {
+        let mut buffer = Buffer::default();
+        buffer.insert_str(Location::default(), "日本");
+
+        assert!(buffer.set_encoding(TextEncoding::DEFAULT_FALLBACK).is_err());
+        assert_eq!(buffer.file_info.encoding, TextEncoding::default());
+        assert!(
+            buffer
+                .set_encoding(TextEncoding::from_label("utf-16le").unwrap())
+                .is_ok()
+        );
+        assert!(buffer.file_info.bom);
+    }
+
+    #[test]
+    fn converting_mixed_line_endings_removes_carriage_returns() {
+        let path = env::temp_dir().join(format!("ed-buffer-{}-convert", std::process::id()));
+        fs::write(&path, "one\r\ntwo\n").unwrap();
+        let mut buffer =
+            Buffer::load(&path.to_string_lossy(), TextEncoding::DEFAULT_FALLBACK).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        buffer.set_line_ending(LineEnding::Crlf);
+        assert_eq!(buffer.file_content(), "one\r\ntwo\r\n");
+        assert!(buffer.dirty);
+    }
+
+    #[test]
+    fn reload_can_be_undone() {
+        let path = env::temp_dir().join(format!("ed-buffer-{}-reload", std::process::id()));
+        fs::write(&path, "one\n").unwrap();
+        let mut buffer =
+            Buffer::load(&path.to_string_lossy(), TextEncoding::DEFAULT_FALLBACK).unwrap();
+        buffer.insert_str(Location::default(), "typed ");
+        fs::write(&path, "changed\non disk\n").unwrap();
+
+        buffer.reload(TextEncoding::default()).unwrap();
+        assert_eq!(buffer.text(), "changed\non disk");
+        assert!(!buffer.dirty);
+
+        buffer.undo();
+        assert_eq!(buffer.text(), "typed one");
+        assert!(buffer.dirty);
+        buffer.redo();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(buffer.text(), "changed\non disk");
+        assert!(!buffer.dirty);
+    }
+}
@@ -0,0 +1,323 @@
+use crate::register::Register;
+use crate::terminal::Terminal;
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::env;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long a clipboard tool may take before the editor gives up on it.
+const TOOL_TIMEOUT: Duration = Duration::from_secs(1);
+
+pub trait ClipboardBackend {
+    fn copy(&mut self, text: &str) -> Result<(), Error>;
+    /// Returns `None` when the backend is not able to read the clipboard back.
+    fn paste(&mut self) -> Result<Option<String>, Error>;
+}
+
+/// Asks the terminal emulator to set the clipboard through an OSC 52 escape sequence.
+/// Works over ssh, but the clipboard can not be read back.
+pub struct Osc52Backend;
+
+impl ClipboardBackend for Osc52Backend {
+    fn copy(&mut self, text: &str) -> Result<(), Error> {
+        Terminal::copy_to_clipboard(text)
+    }
+
+    fn paste(&mut self) -> Result<Option<String>, Error> {
+        Ok(None)
+    }
+}
+
+/// Shells out to a clipboard tool such as `xclip`, `wl-copy` or `pbcopy`.
+#[derive(Clone, Copy)]
+pub struct CommandBackend {
+    copy: &'static [&'static str],
+    paste: &'static [&'static str],
+    required_env: Option<&'static str>,
+}
+
+const CLIPBOARD_TOOLS: [CommandBackend; 3] = [
+    CommandBackend {
+        copy: &["wl-copy"],
+        paste: &["wl-paste", "--no-newline"],
+        required_env: Some("WAYLAND_DISPLAY"),
+    },
+    CommandBackend {
+        copy: &["xclip", "-selection", "clipboard"],
+        paste: &["xclip", "-selection", "clipboard", "-o"],
+        required_env: Some("DISPLAY"),
+    },
+    CommandBackend {
+        copy: &["pbcopy"],
+        paste: &["pbpaste"],
+        required_env: None,
+    },
+];
+
+impl CommandBackend {
+    pub fn detect() -> Option<Self> {
+        CLIPBOARD_TOOLS.into_iter().find(Self::is_available)
+    }
+
+    fn is_available(&self) -> bool {
+        let has_env = self
+            .required_env
+            .is_none_or(|name| env::var_os(name).is_some());
+        let in_path = env::var_os("PATH").is_some_and(|paths| {
+            env::split_paths(&paths).any(|dir| dir.join(self.copy[0]).is_file())
+        });
+        has_env && in_path
+    }
+}
+
+impl ClipboardBackend for CommandBackend {
+    fn copy(&mut self, text: &str) -> Result<(), Error> {
+        run_tool(self.copy, Some(text)).map(|_| ())
+    }
+
+    fn paste(&mut self) -> Result<Option<String>, Error> {
+        let output = run_tool(self.paste, None)?;
+        Ok(Some(String::from_utf8_lossy(&output).replace("\r\n", "\n")))
+    }
+}
+
+/// Runs a clipboard tool, feeding it `input` or collecting its output. The tool is
+/// killed once it takes longer than `TOOL_TIMEOUT`, so a hung tool can not freeze the UI.
+fn run_tool(args: &[&str], input: Option<&str>) -> Result<Vec<u8>, Error> {
+    let (stdin, stdout) = if input.is_some() {
+        // Copy tools may keep serving the selection in the background, holding on to
+        // their output, so it is only read from paste tools.
+        (Stdio::piped(), Stdio::null())
+    } else {
+        (Stdio::null(), Stdio::piped())
+    };
+    let mut child = Command::new(args[0])
+        .args(&args[1..])
+        .stdin(stdin)
+        .stdout(stdout)
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    if let (Some(text), Some(mut stdin)) = (input, child.stdin.take()) {
+        let text = text.to_string();
+        thread::spawn(move || {
+            let _ = stdin.write_all(text.as_bytes());
+        });
+    }
+    let (sender, receiver) = mpsc::channel();
+    if let Some(mut stdout) = child.stdout.take() {
+        thread::spawn(move || {
+            let mut output = Vec::new();
+            let _ = sender.send(stdout.read_to_end(&mut output).map(|_| output));
+        });
+    } else {
+        let _ = sender.send(Ok(Vec::new()));
+    }
+
+    let timed_out = || Error::new(ErrorKind::TimedOut, format!("{} did not respond", args[0]));
+    let deadline = Instant::now() + TOOL_TIMEOUT;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(timed_out());
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    if !status.success() {
+        return Err(Error::other(format!("{} exited with {status}", args[0])));
+    }
+    receiver
+        .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+        .map_err(|_| timed_out())?
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipboardProvider {
+    /// Use a clipboard tool when one is installed, OSC 52 otherwise.
+    #[default]
+    Auto,
+    /// Only use `wl-copy`, `xclip` or `pbcopy`.
+    Command,
+    /// Only use the OSC 52 terminal escape sequence.
+    Osc52,
+    /// Never leave the editor, only the internal register is used.
+    Internal,
+}
+
+/// Copied text always lands in the internal register, and is mirrored to the system
+/// clipboard through `backend`. The register is used whenever the backend is missing,
+/// fails, or can not be read.
+#[derive(Default)]
+pub struct Clipboard {
+    register: Register,
+    backend: Option<Box<dyn ClipboardBackend>>,
+}
+
+impl Clipboard {
+    pub fn new(provider: ClipboardProvider) -> Self {
+        let backend: Option<Box<dyn ClipboardBackend>> = match provider {
+            ClipboardProvider::Auto => Some(CommandBackend::detect().map_or_else(
+                || Box::new(Osc52Backend) as Box<dyn ClipboardBackend>,
+                |backend| Box::new(backend),
+            )),
+            ClipboardProvider::Command => CommandBackend::detect()
+                .map(|backend| Box::new(backend) as Box<dyn ClipboardBackend>),
+            ClipboardProvider::Osc52 => Some(Box::new(Osc52Backend)),
+            ClipboardProvider::Internal => None,
+        };
+        Self::with_backend(backend)
+    }
+
+    pub fn with_backend(backend: Option<Box<dyn ClipboardBackend>>) -> Self {
+        Self {
+            register: Register::default(),
+            backend,
+        }
+    }
+
+    pub fn store(&mut self, register: Register) -> Result<(), Error> {
+        let result = self
+            .backend
+            .as_mut()
+            .map_or(Ok(()), |backend| backend.copy(&register.text));
+        self.register = register;
+        result
+    }
+
+    pub fn load(&mut self) -> Register {
+        let system_text = self
+            .backend
+            .as_mut()
+            .and_then(|backend| backend.paste().ok().flatten());
+
+        match system_text {
+            Some(text) if text != self.register.text => Register {
+                text,
+                linewise: false,
+            },
+            _ => self.register.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+    use std::rc::Rc;
+
+    /// Records what is copied and answers pastes from a script.
+    #[derive(Default)]
+    struct FakeBackend {
+        copies: Rc<RefCell<Vec<String>>>,
+        fail_copy: bool,
+        pastes: VecDeque<Result<Option<String>, Error>>,
+    }
+
+    impl ClipboardBackend for FakeBackend {
+        fn copy(&mut self, text: &str) -> Result<(), Error> {
+            self.copies.borrow_mut().push(text.to_string());
+            if self.fail_copy {
+                Err(Error::other("no clipboard"))
+            } else {
+                Ok(())
+            }
+        }
+
+        fn paste(&mut self) -> Result<Option<String>, Error> {
+            self.pastes.pop_front().unwrap_or(Ok(None))
+        }
+    }
+
+    fn line(text: &str) -> Register {
+        Register {
+            text: text.to_string(),
+            linewise: true,
+        }
+    }
+
+    #[test]
+    fn failed_copy_still_stores_the_register() {
+        let copies = Rc::default();
+        let backend = FakeBackend {
+            copies: Rc::clone(&copies),
+            fail_copy: true,
+            ..FakeBackend::default()
+        };
+        let mut clipboard = Clipboard::with_backend(Some(Box::new(backend)));
+
+        assert!(clipboard.store(line("one")).is_err());
+        assert_eq!(*copies.borrow(), ["one"]);
+        let register = clipboard.load();
+        assert_eq!(register.text, "one");
+        assert!(register.linewise);
+    }
+
+    #[test]
+    fn failed_or_empty_paste_falls_back_to_the_register() {
+        let backend = FakeBackend {
+            pastes: VecDeque::from([Err(Error::new(ErrorKind::NotFound, "gone")), Ok(None)]),
+            ..FakeBackend::default()
+        };
+        let mut clipboard = Clipboard::with_backend(Some(Box::new(backend)));
+        clipboard.store(line("one")).unwrap();
+
+        for _ in 0..2 {
+            let register = clipboard.load();
+            assert_eq!(register.text, "one");
+            assert!(register.linewise);
+        }
+    }
+
+    #[test]
+    fn same_system_text_keeps_linewise() {
+        let backend = FakeBackend {
+            pastes: VecDeque::from([Ok(Some(String::from("one")))]),
+            ..FakeBackend::default()
+        };
+        let mut clipboard = Clipboard::with_backend(Some(Box::new(backend)));
+        clipboard.store(line("one")).unwrap();
+
+        assert!(clipboard.load().linewise);
+    }
+
+    #[test]
+    fn other_system_text_is_pasted_charwise() {
+        let backend = FakeBackend {
+            pastes: VecDeque::from([Ok(Some(String::from("from elsewhere")))]),
+            ..FakeBackend::default()
+        };
+        let mut clipboard = Clipboard::with_backend(Some(Box::new(backend)));
+        clipboard.store(line("one")).unwrap();
+
+        let register = clipboard.load();
+        assert_eq!(register.text, "from elsewhere");
+        assert!(!register.linewise);
+    }
+
+    #[test]
+    fn tool_output_is_returned() {
+        let output = run_tool(&["printf", "pasted"], None).unwrap();
+        assert_eq!(output, b"pasted");
+    }
+
+    #[test]
+    fn hung_tool_times_out() {
+        let started = Instant::now();
+        let error = run_tool(&["sleep", "10"], None).unwrap_err();
+
+        assert_eq!(error.kind(), ErrorKind::TimedOut);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -1,8 +1,9 @@
 use crate::line::Line;
 use crate::ui_component::UiComponent;
 use crate::terminal::Terminal;
+use crate::theme::{Scope, Theme};
 use crate::size::Size;
-use crate::editor_commands::Edit::{self, Insert, Delete, Backspace, Enter, RemoveLine};
+use crate::editor_commands::Edit::{self, Insert, Delete, Backspace, Enter, Undo, Redo, Copy, Cut, Paste, Indent, Dedent};
 use std::cmp::min;
 use std::io::Error;
 
@@ -10,8 +11,10 @@ use std::io::Error;
 pub struct CommandBar {
     prompt: String,
     value: Line,
+    hint: String,
     needs_redraw: bool,
     size: Size,
+    tab_width: usize,
 }
 
 impl UiComponent for CommandBar {
@@ -27,17 +30,22 @@ impl UiComponent for CommandBar {
         self.size = size;
     }
 
-    fn draw(&mut self, position_y: usize) -> Result<(), Error> {
+    fn draw(&mut self, position_y: usize, theme: &Theme) -> Result<(), Error> {
         let value_area = self.size.width.saturating_sub(self.prompt.len());
-        let value_end = self.value.width();
+        let value_end = self.value.width(self.tab_width);
         let value_start = value_end.saturating_sub(value_area);
 
-        let message = format!(
+        let mut message = format!(
             "{}{}",
             self.prompt,
-            self.value.get_visible_graphemes(value_start..value_end)
+            self.value.get_visible_graphemes(value_start..value_end, self.tab_width)
         );
 
+        let hint_area = self.size.width.saturating_sub(message.len());
+        if !self.hint.is_empty() && self.hint.len() < hint_area {
+            message = format!("{message}{:>hint_area$}", self.hint);
+        }
+
         let to_print = if message.len() <= self.size.width {
             message
         } else {
@@ -45,7 +53,7 @@ impl UiComponent for CommandBar {
         };
 
         self.mark_redraw(false);
-        Terminal::print_row(position_y, &to_print)
+        Terminal::print_styled_row(position_y, &to_print, theme.style(Scope::CommandBar))
     }
 }
 
@@ -60,10 +68,15 @@ impl CommandBar {
                 self.value.delete_last();
                 self.mark_redraw(true);
             }
-            Delete | Enter | RemoveLine => {}
+            Delete | Enter | Undo | Redo | Copy | Cut | Paste | Indent | Dedent => {}
         }
     }
 
+    pub fn set_tab_width(&mut self, tab_width: usize) {
+        self.tab_width = tab_width;
+        self.mark_redraw(true);
+    }
+
     pub fn caret_position_col(&self) -> usize {
         let max_width = self.prompt.len().saturating_add(self.value.grapheme_count());
         min(max_width, self.size.width)
@@ -73,6 +86,11 @@ impl CommandBar {
         self.value.to_string()
     }
 
+    pub fn set_value(&mut self, value: &str) {
+        self.value = Line::from(value);
+        self.mark_redraw(true);
+    }
+
     pub fn set_prompt(&mut self, new_prompt: &str) {
         self.prompt = new_prompt.to_string();
         self.mark_redraw(true);
@@ -80,6 +98,15 @@ impl CommandBar {
 
     pub fn clear_value(&mut self) {
         self.value = Line::default();
+        self.hint.clear();
         self.mark_redraw(true);
     }
+
+    /// Shows `hint` right-aligned after the value, e.g. to report an invalid input.
+    pub fn set_hint(&mut self, hint: &str) {
+        if self.hint != hint {
+            self.hint = hint.to_string();
+            self.mark_redraw(true);
+        }
+    }
 }
\ No newline at end of file
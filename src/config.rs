@@ -0,0 +1,107 @@
+use crate::backup::BackupMode;
+use crate::clipboard::ClipboardProvider;
+use crate::line::DEFAULT_TAB_WIDTH;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::read_to_string;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+/// Settings read from `config.toml`, every missing key keeps its default value.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Show the line number gutter when an editor is opened.
+    pub line_numbers: bool,
+    /// Seconds a message stays in the message bar.
+    pub message_timeout: u64,
+    /// How many times ^Q must be pressed to quit an editor with unsaved changes.
+    pub quit_times: u8,
+    /// `dark`, `light` or the path of a TOML theme file.
+    pub theme: String,
+    pub clipboard: ClipboardProvider,
+    /// Columns between two tab stops.
+    pub tab_width: usize,
+    /// Insert spaces instead of a tab character when Tab is pressed.
+    pub soft_tabs: bool,
+    /// Encoding of the files that are neither UTF-8 nor UTF-16, e.g. `latin1` or `shift_jis`.
+    pub fallback_encoding: String,
+    /// Seconds between the writes of unsaved changes to the swap files, `0` turns them off.
+    pub swap_interval: u64,
+    /// Keep the previous version of a file when it is saved: `off`, `single`, `numbered`
+    /// or `timestamped`.
+    pub backup: BackupMode,
+    /// Directory of the backups, an absolute path or one starting with `~/`. The backups
+    /// are written next to the file when it is empty.
+    pub backup_dir: String,
+    /// How many numbered or timestamped backups of a file are kept, `0` keeps all of them.
+    pub backup_keep: usize,
+    /// Key bindings replacing the defaults, e.g. `"Ctrl-K Ctrl-C" = "copy"`.
+    pub keys: BTreeMap<String, String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            line_numbers: false,
+            message_timeout: 3,
+            quit_times: 2,
+            theme: String::from("dark"),
+            clipboard: ClipboardProvider::Auto,
+            tab_width: DEFAULT_TAB_WIDTH,
+            soft_tabs: false,
+            fallback_encoding: String::from("windows-1252"),
+            swap_interval: 4,
+            backup: BackupMode::Off,
+            backup_dir: String::new(),
+            backup_keep: 10,
+            keys: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/ed/config.toml`, falling back to `~/.config/ed/config.toml`.
+    pub fn default_path() -> Option<PathBuf> {
+        let config_home = env::var_os("XDG_CONFIG_HOME")
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config_home.join("ed").join("config.toml"))
+    }
+
+    /// Loads the config file at `path`, or at the default location when `path` is `None`.
+    /// A missing default config file is not an error.
+    pub fn load(path: Option<&str>) -> Result<Self, String> {
+        let (path, required) = match path {
+            Some(path) => (PathBuf::from(path), true),
+            None => match Self::default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+
+        let content = match read_to_string(&path) {
+            Ok(content) => content,
+            Err(error) if error.kind() == ErrorKind::NotFound && !required => {
+                return Ok(Self::default());
+            }
+            Err(error) => {
+                return Err(format!("Could not read config {}: {error}", path.display()));
+            }
+        };
+
+        toml::from_str(&content).map_err(|error| {
+            let location = error.span().map_or_else(String::new, |span| {
+                let line = content[..span.start].matches('\n').count() + 1;
+                format!(" (line {line})")
+            });
+            format!(
+                "Invalid config {}{location}: {}",
+                path.display(),
+                error.message()
+            )
+        })
+    }
+}
@@ -0,0 +1,247 @@
+use std::fmt::Write;
+
+/// Above this many cells of the comparison table the changed middle of two texts is
+/// shown as removed and added as a whole, instead of being compared line by line.
+const MAX_TABLE_SIZE: usize = 4_000_000;
+
+/// Unchanged lines shown around every change.
+const CONTEXT: usize = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DiffLine<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Compares two texts line by line with a longest common subsequence.
+pub fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<DiffLine<'a>> {
+    let old: Vec<&str> = old.split('\n').collect();
+    let new: Vec<&str> = new.split('\n').collect();
+
+    let prefix = old
+        .iter()
+        .zip(&new)
+        .take_while(|(old, new)| old == new)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    let mut diff: Vec<DiffLine> = old[..prefix]
+        .iter()
+        .map(|line| DiffLine::Same(line))
+        .collect();
+    if old_middle.len().saturating_mul(new_middle.len()) > MAX_TABLE_SIZE {
+        diff.extend(old_middle.iter().map(|line| DiffLine::Removed(line)));
+        diff.extend(new_middle.iter().map(|line| DiffLine::Added(line)));
+    } else {
+        diff.extend(common_subsequence_diff(old_middle, new_middle));
+    }
+    diff.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|line| DiffLine::Same(line)),
+    );
+    diff
+}
+
+fn common_subsequence_diff<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<DiffLine<'a>> {
+    // lengths[i][j] is the length of the common subsequence of old[i..] and new[j..].
+    let width = new.len() + 1;
+    let mut lengths = vec![0_usize; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i * width + j] = if old[i] == new[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut diff = Vec::with_capacity(old.len() + new.len());
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            diff.push(DiffLine::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            diff.push(DiffLine::Removed(old[i]));
+            i += 1;
+        } else {
+            diff.push(DiffLine::Added(new[j]));
+            j += 1;
+        }
+    }
+    diff.extend(old[i..].iter().map(|line| DiffLine::Removed(line)));
+    diff.extend(new[j..].iter().map(|line| DiffLine::Added(line)));
+    diff
+}
+
+/// A unified diff of the two texts, `None` when they are equal.
+pub fn unified_diff(old_name: &str, old: &str, new_name: &str, new: &str) -> Option<String> {
+    let diff = diff_lines(old, new);
+    let changed: Vec<usize> = diff
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, DiffLine::Same(_)))
+        .map(|(index, _)| index)
+        .collect();
+    let first = *changed.first()?;
+
+    // Changes closer than twice the context share a hunk.
+    let mut hunks = vec![(first.saturating_sub(CONTEXT), first)];
+    for &index in &changed[1..] {
+        let last = hunks.len() - 1;
+        if index - hunks[last].1 > 2 * CONTEXT {
+            hunks.push((index.saturating_sub(CONTEXT), index));
+        } else {
+            hunks[last].1 = index;
+        }
+    }
+
+    let mut output = format!("--- {old_name}\n+++ {new_name}\n");
+    for (start, last_change) in hunks {
+        let end = last_change.saturating_add(CONTEXT + 1).min(diff.len());
+        let (old_start, new_start) = line_numbers_at(&diff, start);
+        let hunk = &diff[start..end];
+        let old_count = hunk
+            .iter()
+            .filter(|line| !matches!(line, DiffLine::Added(_)))
+            .count();
+        let new_count = hunk
+            .iter()
+            .filter(|line| !matches!(line, DiffLine::Removed(_)))
+            .count();
+        let _ = writeln!(
+            output,
+            "@@ -{},{old_count} +{},{new_count} @@",
+            old_start + 1,
+            new_start + 1
+        );
+        for line in hunk {
+            let (marker, text) = match line {
+                DiffLine::Same(text) => (' ', text),
+                DiffLine::Removed(text) => ('-', text),
+                DiffLine::Added(text) => ('+', text),
+            };
+            output.push(marker);
+            output.push_str(text);
+            output.push('\n');
+        }
+    }
+    Some(output)
+}
+
+/// The zero based line numbers in the old and the new text at `index` of the diff.
+fn line_numbers_at(diff: &[DiffLine], index: usize) -> (usize, usize) {
+    diff[..index]
+        .iter()
+        .fold((0, 0), |(old, new), line| match line {
+            DiffLine::Same(_) => (old + 1, new + 1),
+            DiffLine::Removed(_) => (old + 1, new),
+            DiffLine::Added(_) => (old, new + 1),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered_lines(changed: &[usize]) -> String {
+        let lines: Vec<String> = (1..=20)
+            .map(|number| {
+                if changed.contains(&number) {
+                    format!("changed {number}")
+                } else {
+                    number.to_string()
+                }
+            })
+            .collect();
+        lines.join("\n")
+    }
+
+    fn hunk_headers(diff: &str) -> Vec<&str> {
+        diff.lines().filter(|line| line.starts_with("@@")).collect()
+    }
+
+    #[test]
+    fn equal_texts_have_no_diff() {
+        assert_eq!(unified_diff("a", "same\ntext", "b", "same\ntext"), None);
+    }
+
+    #[test]
+    fn changed_line_is_removed_and_added() {
+        assert_eq!(
+            unified_diff("old", "a\nb\nc", "new", "a\nx\nc").unwrap(),
+            "--- old\n+++ new\n@@ -1,3 +1,3 @@\n a\n-b\n+x\n c\n"
+        );
+    }
+
+    #[test]
+    fn close_changes_share_a_hunk() {
+        let diff = unified_diff(
+            "old",
+            &numbered_lines(&[]),
+            "new",
+            &numbered_lines(&[5, 11]),
+        )
+        .unwrap();
+        assert_eq!(hunk_headers(&diff), ["@@ -2,13 +2,13 @@"]);
+    }
+
+    #[test]
+    fn distant_changes_get_their_own_hunks() {
+        let diff = unified_diff(
+            "old",
+            &numbered_lines(&[]),
+            "new",
+            &numbered_lines(&[5, 12]),
+        )
+        .unwrap();
+        assert_eq!(hunk_headers(&diff), ["@@ -2,7 +2,7 @@", "@@ -9,7 +9,7 @@"]);
+    }
+
+    #[test]
+    fn hunks_are_cut_at_both_ends_of_the_text() {
+        let diff = unified_diff(
+            "old",
+            &numbered_lines(&[]),
+            "new",
+            &numbered_lines(&[1, 20]),
+        )
+        .unwrap();
+        assert_eq!(
+            hunk_headers(&diff),
+            ["@@ -1,4 +1,4 @@", "@@ -17,4 +17,4 @@"]
+        );
+    }
+
+    #[test]
+    fn added_lines_are_counted_in_the_new_text_only() {
+        let diff = unified_diff("old", "a\nb", "new", "a\nx\ny\nb").unwrap();
+        assert_eq!(hunk_headers(&diff), ["@@ -1,2 +1,4 @@"]);
+    }
+
+    #[test]
+    fn line_numbers_count_each_side() {
+        let diff = [
+            DiffLine::Same("a"),
+            DiffLine::Removed("b"),
+            DiffLine::Added("x"),
+            DiffLine::Added("y"),
+            DiffLine::Same("c"),
+        ];
+        assert_eq!(line_numbers_at(&diff, 0), (0, 0));
+        assert_eq!(line_numbers_at(&diff, 2), (2, 1));
+        assert_eq!(line_numbers_at(&diff, 4), (2, 3));
+        assert_eq!(line_numbers_at(&diff, 5), (3, 4));
+    }
+}
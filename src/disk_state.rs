@@ -0,0 +1,125 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::ErrorKind;
+use std::path::Path;
+use std::time::SystemTime;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DiskChange {
+    Unchanged,
+    Modified,
+    Deleted,
+}
+
+/// What the file looked like on disk when it was last read or written, to notice
+/// when another program changes it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DiskState {
+    modified: Option<SystemTime>,
+    len: u64,
+    hash: u64,
+    /// The file was deleted and that was reported. It is compared again once it is back.
+    missing: bool,
+}
+
+impl DiskState {
+    /// The state of the file at `path` that holds `bytes`.
+    pub fn new(path: &Path, bytes: &[u8]) -> Self {
+        let mut hasher = DefaultHasher::new();
+        bytes.hash(&mut hasher);
+        Self {
+            modified: fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok(),
+            len: bytes.len() as u64,
+            hash: hasher.finish(),
+            missing: false,
+        }
+    }
+
+    /// Compares with the file at `path` now and returns the state to record. The content
+    /// is only read when the size or the modification time differ, a file that was merely
+    /// touched is unchanged. A deleted file is reported once, and compared with the last
+    /// known content when it is created again.
+    pub fn check(self, path: &Path) -> (DiskChange, Self) {
+        let metadata = match fs::metadata(path) {
+            Ok(metadata) => metadata,
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                let change = if self.missing {
+                    DiskChange::Unchanged
+                } else {
+                    DiskChange::Deleted
+                };
+                return (
+                    change,
+                    Self {
+                        missing: true,
+                        ..self
+                    },
+                );
+            }
+            Err(_) => return (DiskChange::Unchanged, self),
+        };
+        if !self.missing
+            && self.len == metadata.len()
+            && self.modified.is_some()
+            && self.modified == metadata.modified().ok()
+        {
+            return (DiskChange::Unchanged, self);
+        }
+        let Ok(bytes) = fs::read(path) else {
+            return (DiskChange::Unchanged, self);
+        };
+        let current = Self::new(path, &bytes);
+        if current.hash == self.hash {
+            (DiskChange::Unchanged, current)
+        } else {
+            (DiskChange::Modified, self)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recreated_file_is_compared_again() {
+        let directory = std::env::temp_dir().join(format!("ed-disk-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("file.txt");
+        fs::write(&path, "one").unwrap();
+        let state = DiskState::new(&path, b"one");
+
+        fs::remove_file(&path).unwrap();
+        let (change, state) = state.check(&path);
+        assert_eq!(change, DiskChange::Deleted);
+        let (change, state) = state.check(&path);
+        assert_eq!(change, DiskChange::Unchanged);
+
+        fs::write(&path, "two").unwrap();
+        let (change, state) = state.check(&path);
+        assert_eq!(change, DiskChange::Modified);
+        let (change, _) = state.check(&path);
+        assert_eq!(change, DiskChange::Modified);
+    }
+
+    #[test]
+    fn recreated_file_with_same_content_is_unchanged() {
+        let directory = std::env::temp_dir().join(format!("ed-disk-same-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("file.txt");
+        fs::write(&path, "one").unwrap();
+        let state = DiskState::new(&path, b"one");
+
+        fs::remove_file(&path).unwrap();
+        let (_, state) = state.check(&path);
+        fs::write(&path, "one").unwrap();
+        let (change, state) = state.check(&path);
+        assert_eq!(change, DiskChange::Unchanged);
+        assert!(!state.missing);
+    }
+}
@@ -4,6 +4,8 @@ pub struct DocumentStatus {
     pub number_of_lines: usize,
     pub line_number: usize,
     pub modified: bool,
+    pub file_type: String,
+    pub file_format: String,
 }
 impl DocumentStatus {
     pub(crate) fn line_count_to_string(&self) -> String {
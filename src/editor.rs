@@ -1,37 +1,86 @@
+use crate::backup::BackupPolicy;
 use crate::command_bar::CommandBar;
+use crate::config::Config;
+use crate::disk_state::DiskChange;
 use crate::editor_commands::{
     Command::{self, Edit, Move, System},
-    Edit::Enter,
+    Edit::{Enter, Insert},
     Move::{Down, Up},
-    System::{Dismiss, Quit, Resize, Save, Search, ShowLineNumbers},
+    System::{
+        CycleCaseMode, Dismiss, GotoLine, NewEditor, NextEditor, Open, PreviousEditor, Quit,
+        QuitAll, ReopenWithEncoding, Replace, Resize, Save, SaveAll, SaveWithEncoding, Search,
+        ShowLineNumbers, ToggleLineEnding, ToggleRegex, ToggleWholeWord,
+    },
 };
+use crate::goto_target::GotoTarget;
 use crate::message_bar::MessageBar;
+use crate::path_completion::complete_path;
 use crate::position::Position;
+use crate::register::Register;
 use crate::size::Size;
 use crate::status_bar::StatusBar;
+use crate::swap_file::FoundSwap;
 use crate::terminal::Terminal;
+use crate::text_encoding::TextEncoding;
+use crate::theme::Theme;
 use crate::ui_component::UiComponent;
 use crate::view::{NAME, View};
+use std::path::Path;
+use std::time::Duration;
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum ReplaceStage {
+    Find,
+    With,
+    Confirm,
+}
 
-const TIMES_FOR_QUIT: u8 = 2;
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum EncodingAction {
+    Reopen,
+    Save,
+}
 
-#[derive(PartialEq, Eq, Default)]
+#[derive(PartialEq, Eq, Clone, Copy, Default)]
 enum PromptType {
     Search,
+    Replace(ReplaceStage),
+    GotoLine,
+    Open,
     Save,
+    Encoding(EncodingAction),
+    Recover,
+    DiskChanged,
+    QuitReview,
     #[default]
     None,
 }
 
 impl PromptType {
-    fn is_none(&self) -> bool {
-        *self == Self::None
+    fn is_none(self) -> bool {
+        self == Self::None
     }
 }
 
+/// Where an editor stands while quit all reviews the unsaved changes.
+#[derive(PartialEq, Eq, Clone, Copy, Default)]
+pub enum QuitReview {
+    #[default]
+    Pending,
+    /// The user chose to drop the unsaved changes.
+    Discarded,
+    /// The user backed out, nothing is quit.
+    Cancelled,
+}
+
 #[derive(Default)]
 pub struct Editor {
     pub should_quit: bool,
+    /// Set when a file was chosen in the open prompt, `MultiEditor` decides where it is loaded.
+    pub file_to_open: Option<String>,
+    /// Set when a text such as a diff should be shown, `MultiEditor` opens a new editor for it.
+    pub text_to_show: Option<String>,
+    pub quit_review: QuitReview,
     view: View,
     title: String,
     terminal_size: Size,
@@ -40,11 +89,34 @@ pub struct Editor {
     command_bar: CommandBar,
     prompt_type: PromptType,
     quit_times: u8,
+    times_for_quit: u8,
+    replacement: String,
+    replaced_count: usize,
+    fallback_encoding: TextEncoding,
+    swap_failed: bool,
 }
 
 impl Editor {
-    pub fn new() -> Self {
-        let mut editor = Self::default();
+    pub fn new(config: &Config) -> Self {
+        let mut editor = Self {
+            times_for_quit: config.quit_times,
+            fallback_encoding: TextEncoding::from_label(&config.fallback_encoding)
+                .unwrap_or(TextEncoding::DEFAULT_FALLBACK),
+            ..Self::default()
+        };
+        editor
+            .message_bar
+            .set_timeout(Duration::from_secs(config.message_timeout));
+        editor.view.set_line_numbers(config.line_numbers);
+        editor
+            .view
+            .set_indentation(config.tab_width, config.soft_tabs);
+        editor.command_bar.set_tab_width(config.tab_width);
+        editor.view.set_backup(BackupPolicy::new(
+            config.backup,
+            &config.backup_dir,
+            config.backup_keep,
+        ));
         let size = Terminal::size().unwrap_or_default();
         editor.handle_resize_command(size);
         editor.refresh_status();
@@ -54,7 +126,7 @@ impl Editor {
         editor
     }
 
-    fn in_prompt(&self) -> bool {
+    pub fn in_prompt(&self) -> bool {
         !self.prompt_type.is_none()
     }
 
@@ -78,7 +150,7 @@ impl Editor {
         }
     }
 
-    pub fn refresh_screen(&mut self) {
+    pub fn refresh_screen(&mut self, theme: &Theme) {
         if self.terminal_size.height == 0 || self.terminal_size.width == 0 {
             return;
         }
@@ -88,18 +160,28 @@ impl Editor {
         let _ = Terminal::hide_caret();
 
         match self.prompt_type {
-            PromptType::None => self.message_bar.render(bottom_bar_row),
-            PromptType::Save | PromptType::Search => self.command_bar.render(bottom_bar_row),
+            PromptType::None => self.message_bar.render(bottom_bar_row, theme),
+            PromptType::Save
+            | PromptType::Search
+            | PromptType::Replace(_)
+            | PromptType::GotoLine
+            | PromptType::Open
+            | PromptType::Encoding(_)
+            | PromptType::Recover
+            | PromptType::DiskChanged
+            | PromptType::QuitReview => {
+                self.command_bar.render(bottom_bar_row, theme);
+            }
         }
 
         if self.terminal_size.height > 1 {
             self.status_bar
-                .render(self.terminal_size.height.saturating_sub(2));
+                .render(self.terminal_size.height.saturating_sub(2), theme);
             self.refresh_status();
         }
 
         if self.terminal_size.height > 2 {
-            self.view.render(0);
+            self.view.render(0, theme);
         }
 
         let new_caret_position = if self.in_prompt() {
@@ -116,15 +198,166 @@ impl Editor {
         let _ = Terminal::execute();
     }
 
-    pub(crate) fn load(&mut self, file_name: &str) {
-        if self.view.load(file_name).is_err() {
+    pub(crate) fn load(&mut self, file_name: &str, target: Option<GotoTarget>) {
+        if let Err(error) = self.view.load(file_name, self.fallback_encoding) {
             self.message_bar
-                .update_message(&format!("ERROR: Failed to read file {file_name}"));
+                .update_message(&format!("ERROR: Failed to read file {file_name}: {error}"));
         } else {
+            if let Some(target) = target {
+                self.view.goto(target);
+            }
+            match self.view.found_swap() {
+                Some(FoundSwap {
+                    in_use_by: Some(owner),
+                    ..
+                }) => {
+                    let message = format!("WARNING: {file_name} is also being edited by {owner}");
+                    self.message_bar.update_message(&message);
+                }
+                Some(_) => self.set_prompt(PromptType::Recover),
+                None => {}
+            }
             self.refresh_status();
         }
     }
 
+    /// Shows `text` that is not backed by a file, e.g. a diff.
+    pub fn show_text(&mut self, text: &str) {
+        self.view.show_text(text);
+        self.refresh_status();
+    }
+
+    /// Writes the unsaved changes to the swap file. A failure is reported once, until
+    /// a write succeeds again.
+    pub fn write_swap(&mut self) {
+        match self.view.write_swap() {
+            Ok(()) => self.swap_failed = false,
+            Err(error) => {
+                if !self.swap_failed {
+                    self.swap_failed = true;
+                    self.message_bar.update_message(&format!(
+                        "WARNING: Could not write the swap file: {error}"
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Reacts to another program changing the file: an unmodified buffer is reloaded,
+    /// otherwise the user decides. Nothing happens while a prompt is open.
+    pub fn check_disk(&mut self) {
+        if self.in_prompt() {
+            return;
+        }
+        match self.view.check_disk() {
+            DiskChange::Unchanged => {}
+            DiskChange::Deleted => self
+                .message_bar
+                .update_message("WARNING: The file was deleted, saving creates it again"),
+            DiskChange::Modified if !self.view.get_status().modified => self.reload(),
+            DiskChange::Modified => self.set_prompt(PromptType::DiskChanged),
+        }
+    }
+
+    fn reload(&mut self) {
+        match self.view.reload() {
+            Ok(()) => {
+                self.message_bar
+                    .update_message("Reloaded, the file changed on disk");
+                self.refresh_status();
+            }
+            Err(error) => self
+                .message_bar
+                .update_message(&format!("ERROR: Failed to reload file: {error}")),
+        }
+    }
+
+    /// Removes the swap file when the editor is closed, its changes are dropped.
+    pub fn close(&mut self) {
+        self.view.remove_swap();
+    }
+
+    pub fn is_editing(&self, path: &Path) -> bool {
+        self.view.is_editing(path)
+    }
+
+    pub fn file_name(&self) -> String {
+        self.view.get_status().file_name
+    }
+
+    /// True when quitting would lose changes, unless the user already chose to drop them.
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.view.get_status().modified && self.quit_review != QuitReview::Discarded
+    }
+
+    /// Saves the file if it has unsaved changes, returning whether it was written.
+    pub fn save_modified(&mut self) -> Result<bool, String> {
+        if !self.view.get_status().modified {
+            return Ok(false);
+        }
+        if !self.view.is_file_loaded() {
+            return Err(String::from("no file name"));
+        }
+        if self.view.check_disk() == DiskChange::Modified {
+            return Err(String::from("changed on disk"));
+        }
+        self.view.save().map_err(|error| error.to_string())?;
+        self.refresh_status();
+        Ok(true)
+    }
+
+    /// Asks what to do with the unsaved changes before quitting, `unsaved` names all
+    /// the files that have some.
+    pub fn review_for_quit(&mut self, unsaved: &[String]) {
+        self.set_prompt(PromptType::QuitReview);
+        self.command_bar.set_prompt(&format!(
+            "Unsaved changes in {}. Save {}? (s)ave (d)iscard, Esc cancels ",
+            unsaved.join(", "),
+            self.file_name()
+        ));
+    }
+
+    /// True for a fresh editor that has no file and no text typed into it.
+    pub fn is_untouched(&self) -> bool {
+        let status = self.view.get_status();
+        !self.view.is_file_loaded() && status.number_of_lines == 0 && !status.modified
+    }
+
+    pub fn copy(&mut self) -> Option<Register> {
+        if self.in_prompt() {
+            return None;
+        }
+        let register = self.view.copy()?;
+        self.message_bar.update_message(if register.linewise {
+            "Line copied"
+        } else {
+            "Selection copied"
+        });
+        Some(register)
+    }
+
+    pub fn cut(&mut self) -> Option<Register> {
+        if self.in_prompt() {
+            return None;
+        }
+        self.reset_quit_times();
+        let register = self.view.cut()?;
+        self.message_bar.update_message(if register.linewise {
+            "Line cut"
+        } else {
+            "Selection cut"
+        });
+        Some(register)
+    }
+
+    pub fn paste(&mut self, register: &Register) {
+        if self.in_prompt() {
+            return;
+        }
+        self.reset_quit_times();
+        self.view.paste(register);
+    }
+
     fn process_command_no_prompt(&mut self, command: Command) {
         if matches!(command, System(Quit)) {
             self.handle_quit();
@@ -133,10 +366,31 @@ impl Editor {
         self.reset_quit_times();
 
         match command {
-            System(Quit | Resize(_) | Dismiss) => {}
+            System(
+                Quit | Resize(_) | Dismiss | ToggleRegex | CycleCaseMode | ToggleWholeWord
+                | NextEditor | PreviousEditor | NewEditor | SaveAll | QuitAll,
+            ) => {}
             System(Search) => self.set_prompt(PromptType::Search),
+            System(Replace) => self.set_prompt(PromptType::Replace(ReplaceStage::Find)),
+            System(GotoLine) => self.set_prompt(PromptType::GotoLine),
+            System(Open) => self.set_prompt(PromptType::Open),
+            System(ReopenWithEncoding) => {
+                if self.view.is_file_loaded() {
+                    self.set_prompt(PromptType::Encoding(EncodingAction::Reopen));
+                } else {
+                    self.message_bar.update_message("No file to reopen");
+                }
+            }
+            System(SaveWithEncoding) => {
+                self.set_prompt(PromptType::Encoding(EncodingAction::Save));
+            }
             System(Save) => self.handle_save_command(),
             System(ShowLineNumbers) => self.toggle_line_numbers(),
+            System(ToggleLineEnding) => {
+                let line_ending = self.view.toggle_line_ending();
+                self.message_bar
+                    .update_message(&format!("Line endings converted to {line_ending}"));
+            }
             Edit(edit_command) => self.view.handle_edit_command(edit_command),
             Move(move_command) => self.view.handle_move_command(move_command),
         }
@@ -144,7 +398,13 @@ impl Editor {
 
     fn process_command_during_save(&mut self, command: Command) {
         match command {
-            System(Quit | Resize(_) | Search | Save | ShowLineNumbers) | Move(_) => {}
+            System(
+                Quit | Resize(_) | Search | Replace | GotoLine | Open | Save | ShowLineNumbers
+                | ToggleRegex | CycleCaseMode | ToggleWholeWord | ToggleLineEnding
+                | ReopenWithEncoding | SaveWithEncoding | NextEditor | PreviousEditor | NewEditor
+                | SaveAll | QuitAll,
+            )
+            | Move(_) => {}
             System(Dismiss) => {
                 self.set_prompt(PromptType::None);
                 self.message_bar.update_message("Save aborted!");
@@ -158,6 +418,230 @@ impl Editor {
         }
     }
 
+    fn process_command_during_goto_line(&mut self, command: Command) {
+        match command {
+            System(
+                Quit | Resize(_) | Search | Replace | GotoLine | Open | Save | ShowLineNumbers
+                | ToggleRegex | CycleCaseMode | ToggleWholeWord | ToggleLineEnding
+                | ReopenWithEncoding | SaveWithEncoding | NextEditor | PreviousEditor | NewEditor
+                | SaveAll | QuitAll,
+            )
+            | Move(_) => {}
+            System(Dismiss) => self.set_prompt(PromptType::None),
+            Edit(Enter) => match self.command_bar.value().parse::<GotoTarget>() {
+                Ok(target) => {
+                    self.view.goto(target);
+                    self.set_prompt(PromptType::None);
+                }
+                Err(error) => self.command_bar.set_hint(&error),
+            },
+            Edit(edit_command) => {
+                self.command_bar.handle_edit_command(edit_command);
+                self.command_bar.set_hint("");
+            }
+        }
+    }
+
+    fn process_command_during_open(&mut self, command: Command) {
+        match command {
+            System(
+                Quit | Resize(_) | Search | Replace | GotoLine | Open | Save | ShowLineNumbers
+                | ToggleRegex | CycleCaseMode | ToggleWholeWord | ToggleLineEnding
+                | ReopenWithEncoding | SaveWithEncoding | NextEditor | PreviousEditor | NewEditor
+                | SaveAll | QuitAll,
+            )
+            | Move(_) => {}
+            System(Dismiss) => self.set_prompt(PromptType::None),
+            Edit(Enter) => {
+                let file_name = self.command_bar.value();
+                if Path::new(&file_name).is_file() {
+                    self.file_to_open = Some(file_name);
+                    self.set_prompt(PromptType::None);
+                } else {
+                    self.command_bar.set_hint("not a file");
+                }
+            }
+            Edit(Insert('\t')) => {
+                let completion = complete_path(&self.command_bar.value());
+                self.command_bar.set_value(&completion.completed);
+                let hint = if completion.candidates.len() > 1 {
+                    completion.candidates.join(" ")
+                } else {
+                    String::new()
+                };
+                self.command_bar.set_hint(&hint);
+            }
+            Edit(edit_command) => {
+                self.command_bar.handle_edit_command(edit_command);
+                self.command_bar.set_hint("");
+            }
+        }
+    }
+
+    fn process_command_during_encoding(&mut self, action: EncodingAction, command: Command) {
+        match command {
+            System(
+                Quit | Resize(_) | Search | Replace | GotoLine | Open | Save | ShowLineNumbers
+                | ToggleRegex | CycleCaseMode | ToggleWholeWord | ToggleLineEnding
+                | ReopenWithEncoding | SaveWithEncoding | NextEditor | PreviousEditor | NewEditor
+                | SaveAll | QuitAll,
+            )
+            | Move(_) => {}
+            System(Dismiss) => self.set_prompt(PromptType::None),
+            Edit(Enter) => {
+                let label = self.command_bar.value();
+                let Some(encoding) = TextEncoding::from_label(&label) else {
+                    self.command_bar.set_hint("unknown encoding");
+                    return;
+                };
+                match action {
+                    EncodingAction::Reopen => self.reopen_with_encoding(encoding),
+                    EncodingAction::Save => match self.view.set_encoding(encoding) {
+                        Ok(()) => {
+                            self.set_prompt(PromptType::None);
+                            self.handle_save_command();
+                        }
+                        Err(error) => self.command_bar.set_hint(&error.to_string()),
+                    },
+                }
+            }
+            Edit(edit_command) => {
+                self.command_bar.handle_edit_command(edit_command);
+                self.command_bar.set_hint("");
+            }
+        }
+    }
+
+    fn reopen_with_encoding(&mut self, encoding: TextEncoding) {
+        if self.view.get_status().modified {
+            self.command_bar
+                .set_hint("unsaved changes, save or undo them first");
+            return;
+        }
+        match self.view.reload_with_encoding(encoding) {
+            Ok(()) => {
+                self.set_prompt(PromptType::None);
+                self.message_bar
+                    .update_message(&format!("Reopened as {encoding}"));
+                self.refresh_status();
+            }
+            Err(error) => self.command_bar.set_hint(&error.to_string()),
+        }
+    }
+
+    fn process_command_during_recover(&mut self, command: Command) {
+        match command {
+            Edit(Insert('r')) => match self.view.recover_swap() {
+                Ok(()) => {
+                    self.set_prompt(PromptType::None);
+                    self.message_bar
+                        .update_message("Unsaved changes recovered, save to keep them");
+                }
+                Err(error) => self.command_bar.set_hint(&error.to_string()),
+            },
+            Edit(Insert('d')) => match self.view.found_swap_diff() {
+                Ok(Some(diff)) => self.text_to_show = Some(diff),
+                Ok(None) => self.command_bar.set_hint("same text as the file"),
+                Err(error) => self.command_bar.set_hint(&error.to_string()),
+            },
+            Edit(Insert('x')) => match self.view.discard_swap() {
+                Ok(()) => {
+                    self.set_prompt(PromptType::None);
+                    self.message_bar.update_message("Swap file discarded");
+                }
+                Err(error) => self.command_bar.set_hint(&error.to_string()),
+            },
+            System(Dismiss) => {
+                self.set_prompt(PromptType::None);
+                let name = self
+                    .view
+                    .swap_path()
+                    .and_then(Path::file_name)
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                self.message_bar
+                    .update_message(&format!("Swap file kept, changes are swapped to {name}"));
+            }
+            Edit(_)
+            | Move(_)
+            | System(
+                Quit | Resize(_) | Search | Replace | GotoLine | Open | Save | ShowLineNumbers
+                | ToggleRegex | CycleCaseMode | ToggleWholeWord | ToggleLineEnding
+                | ReopenWithEncoding | SaveWithEncoding | NextEditor | PreviousEditor | NewEditor
+                | SaveAll | QuitAll,
+            ) => {}
+        }
+    }
+
+    fn process_command_during_disk_changed(&mut self, command: Command) {
+        match command {
+            Edit(Insert('r')) => {
+                self.set_prompt(PromptType::None);
+                self.reload();
+            }
+            Edit(Insert('o')) => {
+                self.set_prompt(PromptType::None);
+                self.save(None);
+            }
+            Edit(Insert('d')) => match self.view.disk_diff() {
+                Ok(Some(diff)) => self.text_to_show = Some(diff),
+                Ok(None) => self.command_bar.set_hint("same text as the file"),
+                Err(error) => self.command_bar.set_hint(&error.to_string()),
+            },
+            System(Dismiss) => {
+                self.view.ignore_disk_change();
+                self.set_prompt(PromptType::None);
+                self.message_bar
+                    .update_message("Changes kept, saving overwrites the file");
+            }
+            Edit(_)
+            | Move(_)
+            | System(
+                Quit | Resize(_) | Search | Replace | GotoLine | Open | Save | ShowLineNumbers
+                | ToggleRegex | CycleCaseMode | ToggleWholeWord | ToggleLineEnding
+                | ReopenWithEncoding | SaveWithEncoding | NextEditor | PreviousEditor | NewEditor
+                | SaveAll | QuitAll,
+            ) => {}
+        }
+    }
+
+    fn process_command_during_quit_review(&mut self, command: Command) {
+        match command {
+            Edit(Insert('s')) => {
+                if self.view.is_file_loaded() && self.view.check_disk() != DiskChange::Modified {
+                    match self.view.save() {
+                        Ok(()) => {
+                            self.set_prompt(PromptType::None);
+                            self.refresh_status();
+                        }
+                        Err(error) => self
+                            .command_bar
+                            .set_hint(&format!("failed to save: {error}")),
+                    }
+                } else {
+                    self.set_prompt(PromptType::None);
+                    self.handle_save_command();
+                }
+            }
+            Edit(Insert('d')) => {
+                self.quit_review = QuitReview::Discarded;
+                self.set_prompt(PromptType::None);
+            }
+            System(Dismiss) => {
+                self.quit_review = QuitReview::Cancelled;
+                self.set_prompt(PromptType::None);
+            }
+            Edit(_)
+            | Move(_)
+            | System(
+                Quit | Resize(_) | Search | Replace | GotoLine | Open | Save | ShowLineNumbers
+                | ToggleRegex | CycleCaseMode | ToggleWholeWord | ToggleLineEnding
+                | ReopenWithEncoding | SaveWithEncoding | NextEditor | PreviousEditor | NewEditor
+                | SaveAll | QuitAll,
+            ) => {}
+        }
+    }
+
     fn process_command_during_search(&mut self, command: Command) {
         match command {
             System(Dismiss) => {
@@ -170,15 +654,125 @@ impl Editor {
             }
             Edit(edit_command) => {
                 self.command_bar.handle_edit_command(edit_command);
-                let query = self.command_bar.value();
-                self.view.search(&query);
+                self.update_search();
             }
-            Move(Down) => self.view.search_next(),
-            Move(Up) => self.view.search_prev(),
-            Move(_) | System(Quit | Resize(_) | Search | Save | ShowLineNumbers) => {}
+            System(ToggleRegex) => {
+                self.view.toggle_search_regex();
+                self.update_search();
+            }
+            System(CycleCaseMode) => {
+                self.view.cycle_search_case_mode();
+                self.update_search();
+            }
+            System(ToggleWholeWord) => {
+                self.view.toggle_search_whole_word();
+                self.update_search();
+            }
+            Move(Down) => {
+                self.view.search_next();
+                self.update_match_counts();
+            }
+            Move(Up) => {
+                self.view.search_prev();
+                self.update_match_counts();
+            }
+            Move(_)
+            | System(
+                Quit | Resize(_) | Search | Replace | GotoLine | Open | Save | ShowLineNumbers
+                | ToggleLineEnding | ReopenWithEncoding | SaveWithEncoding | NextEditor
+                | PreviousEditor | NewEditor | SaveAll | QuitAll,
+            ) => {}
+        }
+    }
+
+    fn process_command_during_replace(&mut self, stage: ReplaceStage, command: Command) {
+        match (stage, command) {
+            (ReplaceStage::Find | ReplaceStage::With, System(Dismiss)) => {
+                self.set_prompt(PromptType::None);
+                self.view.dimiss_search();
+            }
+            (ReplaceStage::Find, Edit(Enter)) => {
+                if self
+                    .view
+                    .search_match_counts()
+                    .is_some_and(|(_, total)| total > 0)
+                {
+                    self.set_prompt(PromptType::Replace(ReplaceStage::With));
+                }
+            }
+            (ReplaceStage::Find, _) => self.process_command_during_search(command),
+            (ReplaceStage::With, Edit(Enter)) => {
+                self.replacement = self.command_bar.value();
+                self.replaced_count = 0;
+                self.set_prompt(PromptType::Replace(ReplaceStage::Confirm));
+                self.update_match_counts();
+            }
+            (ReplaceStage::With, Edit(edit_command)) => {
+                self.command_bar.handle_edit_command(edit_command);
+            }
+            (ReplaceStage::Confirm, Edit(Insert('y'))) => {
+                if self.view.replace_current(&self.replacement) {
+                    self.replaced_count += 1;
+                }
+                if self
+                    .view
+                    .search_match_counts()
+                    .is_none_or(|(_, total)| total == 0)
+                {
+                    self.finish_replace();
+                } else {
+                    self.update_match_counts();
+                }
+            }
+            (ReplaceStage::Confirm, Edit(Insert('n'))) => {
+                self.view.search_next();
+                self.update_match_counts();
+            }
+            (ReplaceStage::Confirm, Edit(Insert('a'))) => {
+                self.replaced_count += self.view.replace_all(&self.replacement);
+                self.finish_replace();
+            }
+            (ReplaceStage::Confirm, Edit(Insert('q')) | System(Dismiss)) => self.finish_replace(),
+            (ReplaceStage::With | ReplaceStage::Confirm, _) => {}
+        }
+    }
+
+    fn finish_replace(&mut self) {
+        self.set_prompt(PromptType::None);
+        self.view.exit_search();
+        self.message_bar
+            .update_message(&format!("Replaced {} occurrence(s)", self.replaced_count));
+    }
+
+    fn update_search(&mut self) {
+        let query = self.command_bar.value();
+        let result = self.view.search(&query);
+        let name = if matches!(self.prompt_type, PromptType::Replace(_)) {
+            "Replace"
+        } else {
+            "Find"
+        };
+        self.command_bar
+            .set_prompt(&format!("{name}{}: ", self.view.search_options().label()));
+
+        match result {
+            Ok(()) => self.update_match_counts(),
+            Err(error) => self
+                .command_bar
+                .set_hint(&format!("Invalid pattern: {error}")),
         }
     }
 
+    fn update_match_counts(&mut self) {
+        let hint = match self.view.search_match_counts() {
+            None => String::new(),
+            Some((_, 0)) => String::from("no matches"),
+            Some((Some(current), total)) => format!("match {} of {total}", current + 1),
+            Some((None, total)) => format!("{total} matches"),
+        };
+        self.command_bar.set_hint(&hint);
+    }
+
     pub fn process_command(&mut self, command: Command) {
         if let System(Resize(size)) = command {
             self.handle_resize_command(size);
@@ -189,6 +783,13 @@ impl Editor {
             PromptType::None => self.process_command_no_prompt(command),
             PromptType::Save => self.process_command_during_save(command),
             PromptType::Search => self.process_command_during_search(command),
+            PromptType::Replace(stage) => self.process_command_during_replace(stage, command),
+            PromptType::GotoLine => self.process_command_during_goto_line(command),
+            PromptType::Open => self.process_command_during_open(command),
+            PromptType::Encoding(action) => self.process_command_during_encoding(action, command),
+            PromptType::Recover => self.process_command_during_recover(command),
+            PromptType::DiskChanged => self.process_command_during_disk_changed(command),
+            PromptType::QuitReview => self.process_command_during_quit_review(command),
         }
     }
 
@@ -208,12 +809,14 @@ impl Editor {
     }
 
     pub fn handle_quit(&mut self) {
-        if !self.view.get_status().modified || self.quit_times + 1 == TIMES_FOR_QUIT {
+        if !self.view.get_status().modified
+            || self.quit_times.saturating_add(1) >= self.times_for_quit
+        {
             self.should_quit = true;
         } else {
             self.message_bar.update_message(&format!(
                 "WARNING: File has unsaved changes. Press ^Q {} more times to exit",
-                TIMES_FOR_QUIT - self.quit_times - 1
+                self.times_for_quit - self.quit_times - 1
             ));
             self.quit_times += 1;
         }
@@ -226,19 +829,24 @@ impl Editor {
             self.view.save()
         };
 
-        if result.is_err() {
-            self.message_bar.update_message("Failed to save file");
-        } else {
-            self.message_bar.update_message("File saved successfully");
-            self.refresh_status();
+        match result {
+            Ok(()) => {
+                self.message_bar.update_message("File saved successfully");
+                self.refresh_status();
+            }
+            Err(error) => self
+                .message_bar
+                .update_message(&format!("Failed to save file: {error}")),
         }
     }
 
     fn handle_save_command(&mut self) {
-        if self.view.is_file_loaded() {
-            self.save(None);
-        } else {
+        if !self.view.is_file_loaded() {
             self.set_prompt(PromptType::Save);
+        } else if self.view.check_disk() == DiskChange::Modified {
+            self.set_prompt(PromptType::DiskChanged);
+        } else {
+            self.save(None);
         }
     }
 
@@ -254,10 +862,46 @@ impl Editor {
         match prompt_type {
             PromptType::None => self.message_bar.set_needs_redraw(true),
             PromptType::Save => self.command_bar.set_prompt("Save as: "),
+            PromptType::GotoLine => self.command_bar.set_prompt("Go to line: "),
+            PromptType::Open => self.command_bar.set_prompt("Open: "),
+            PromptType::Encoding(EncodingAction::Reopen) => {
+                self.command_bar.set_prompt("Reopen with encoding: ");
+            }
+            PromptType::Encoding(EncodingAction::Save) => {
+                self.command_bar.set_prompt("Save with encoding: ");
+            }
             PromptType::Search => {
                 self.view.enter_search();
                 self.command_bar.set_prompt("Find: ");
             }
+            PromptType::Replace(ReplaceStage::Find) => {
+                self.view.enter_search();
+                self.command_bar.set_prompt("Replace: ");
+            }
+            PromptType::Replace(ReplaceStage::With) => {
+                self.command_bar.set_prompt("Replace with: ");
+            }
+            PromptType::Replace(ReplaceStage::Confirm) => self
+                .command_bar
+                .set_prompt("Replace this match? (y)es (n)o (a)ll (q)uit "),
+            PromptType::Recover => {
+                let name = self
+                    .view
+                    .found_swap()
+                    .and_then(|found| found.path.file_name())
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                self.command_bar.set_prompt(&format!(
+                    "Swap file {name} found: (r)ecover (d)iff (x) discard, Esc keeps it "
+                ));
+            }
+            PromptType::DiskChanged => {
+                let name = self.view.get_status().file_name;
+                self.command_bar.set_prompt(&format!(
+                    "{name} changed on disk: (r)eload (o)verwrite (d)iff, Esc keeps your changes "
+                ));
+            }
+            PromptType::QuitReview => self.command_bar.set_prompt("Save? (s)ave (d)iscard "),
         }
         self.command_bar.clear_value();
         self.prompt_type = prompt_type;
@@ -1,5 +1,5 @@
 use crate::size::Size;
-use crossterm::event::{Event, KeyCode, KeyModifiers, KeyEvent};
+use std::str::FromStr;
 
 #[derive(Copy, Clone)]
 pub enum Move {
@@ -13,31 +13,33 @@ pub enum Move {
     End,
     WordJumpRight,
     WordJumpLeft,
+    SelectUp,
+    SelectDown,
+    SelectLeft,
+    SelectRight,
+    SelectPageUp,
+    SelectPageDown,
+    SelectHome,
+    SelectEnd,
+    SelectWordJumpRight,
+    SelectWordJumpLeft,
 }
 
-impl TryFrom<KeyEvent> for Move {
-    type Error = String;
-
-    fn try_from(event: KeyEvent) -> Result<Self, String> {
-        let KeyEvent {
-            code,
-            modifiers,
-            ..
-        } = event;
-        
-        match (code, modifiers) {
-            (KeyCode::Right, KeyModifiers::CONTROL) => Ok(Self::WordJumpRight),
-            (KeyCode::Left, KeyModifiers::CONTROL) => Ok(Self::WordJumpLeft),
-            (KeyCode::Up, _) => Ok(Self::Up),
-            (KeyCode::Down, _) => Ok(Self::Down),
-            (KeyCode::Left, _) => Ok(Self::Left),
-            (KeyCode::Right, _) => Ok(Self::Right),
-            (KeyCode::PageDown, _) => Ok(Self::PageDown),
-            (KeyCode::PageUp, _) => Ok(Self::PageUp),
-            (KeyCode::Home, _) => Ok(Self::Home),
-            (KeyCode::End, _) => Ok(Self::End),
-            _ => Err(format!("Unsupported key code {code:?} or modifier {modifiers:?}"))
-        }
+impl Move {
+    pub const fn is_selection(self) -> bool {
+        matches!(
+            self,
+            Self::SelectUp
+                | Self::SelectDown
+                | Self::SelectLeft
+                | Self::SelectRight
+                | Self::SelectPageUp
+                | Self::SelectPageDown
+                | Self::SelectHome
+                | Self::SelectEnd
+                | Self::SelectWordJumpRight
+                | Self::SelectWordJumpLeft
+        )
     }
 }
 
@@ -47,29 +49,13 @@ pub enum Edit {
     Delete,
     Enter,
     Backspace,
-    RemoveLine,
-}
-
-impl TryFrom<KeyEvent> for Edit {
-    type Error = String;
-
-    fn try_from(event: KeyEvent) -> Result<Self, Self::Error> {
-        let KeyEvent {
-            code,
-            modifiers,
-            ..
-        } = event;
-
-        match (code, modifiers) {
-            (KeyCode::Char('x'), KeyModifiers::CONTROL) => Ok(Self::RemoveLine),
-            (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => Ok(Self::Insert(c)),
-            (KeyCode::Delete, _) => Ok(Self::Delete),
-            (KeyCode::Backspace, _) => Ok(Self::Backspace),
-            (KeyCode::Tab, _) => Ok(Self::Insert('\t')),
-            (KeyCode::Enter, _) => Ok(Self::Enter),
-            _ => Err(format!("Unsupported key code {code:?} or modifier {modifiers:?}"))
-        }
-    }
+    Undo,
+    Redo,
+    Copy,
+    Cut,
+    Paste,
+    Indent,
+    Dedent,
 }
 
 #[derive(Copy, Clone)]
@@ -80,28 +66,20 @@ pub enum System {
     ShowLineNumbers,
     Dismiss,
     Search,
-}
-
-
-impl TryFrom<KeyEvent> for System {
-    type Error = String;
-
-    fn try_from(event: KeyEvent) -> Result<Self, Self::Error> {
-        let KeyEvent {
-            code,
-            modifiers,
-            ..
-        } = event;
-
-        match (code, modifiers) {
-            (KeyCode::Char('q'), KeyModifiers::CONTROL) => Ok(Self::Quit),
-            (KeyCode::Char('s'), KeyModifiers::CONTROL) => Ok(Self::Save),
-            (KeyCode::Char('l'), KeyModifiers::CONTROL) => Ok(Self::ShowLineNumbers),
-            (KeyCode::Char('f'), KeyModifiers::CONTROL) => Ok(Self::Search),
-            (KeyCode::Esc, _) => Ok(Self::Dismiss),
-            _ => Err(format!("Key Code is not supported: {code:?}")),
-        }
-    }
+    Replace,
+    GotoLine,
+    Open,
+    ToggleRegex,
+    CycleCaseMode,
+    ToggleWholeWord,
+    ToggleLineEnding,
+    ReopenWithEncoding,
+    SaveWithEncoding,
+    NextEditor,
+    PreviousEditor,
+    NewEditor,
+    SaveAll,
+    QuitAll,
 }
 
 #[derive(Copy, Clone)]
@@ -111,21 +89,68 @@ pub enum Command {
     System(System),
 }
 
-impl TryFrom<Event> for Command {
-    type Error = String;
-    fn try_from(event: Event) -> Result<Self, Self::Error> {
-        match event {
-            Event::Key(key_event) => Edit::try_from(key_event)
-                .map(Command::Edit)
-                .or_else(|_| Move::try_from(key_event).map(Command::Move))
-                .or_else(|_| System::try_from(key_event).map(Command::System))
-                .map_err(|_err| format!("Event not supported: {key_event:?}")),
-                Event::Resize(width_u16, height_u16) => Ok(Self::System(System::Resize(Size {
-                    width: width_u16 as usize,
-                    height: height_u16 as usize,
-                }))),
-                _ => Err(format!("The given event is currently not supported. Recived event: {event:?}")),
-        }
+/// Names used to bind commands to keys in the config file.
+const COMMAND_NAMES: &[(&str, Command)] = &[
+    ("move_up", Command::Move(Move::Up)),
+    ("move_down", Command::Move(Move::Down)),
+    ("move_left", Command::Move(Move::Left)),
+    ("move_right", Command::Move(Move::Right)),
+    ("page_up", Command::Move(Move::PageUp)),
+    ("page_down", Command::Move(Move::PageDown)),
+    ("home", Command::Move(Move::Home)),
+    ("end", Command::Move(Move::End)),
+    ("word_jump_right", Command::Move(Move::WordJumpRight)),
+    ("word_jump_left", Command::Move(Move::WordJumpLeft)),
+    ("select_up", Command::Move(Move::SelectUp)),
+    ("select_down", Command::Move(Move::SelectDown)),
+    ("select_left", Command::Move(Move::SelectLeft)),
+    ("select_right", Command::Move(Move::SelectRight)),
+    ("select_page_up", Command::Move(Move::SelectPageUp)),
+    ("select_page_down", Command::Move(Move::SelectPageDown)),
+    ("select_home", Command::Move(Move::SelectHome)),
+    ("select_end", Command::Move(Move::SelectEnd)),
+    ("select_word_jump_right", Command::Move(Move::SelectWordJumpRight)),
+    ("select_word_jump_left", Command::Move(Move::SelectWordJumpLeft)),
+    ("insert_tab", Command::Edit(Edit::Insert('\t'))),
+    ("delete", Command::Edit(Edit::Delete)),
+    ("enter", Command::Edit(Edit::Enter)),
+    ("backspace", Command::Edit(Edit::Backspace)),
+    ("undo", Command::Edit(Edit::Undo)),
+    ("redo", Command::Edit(Edit::Redo)),
+    ("copy", Command::Edit(Edit::Copy)),
+    ("cut", Command::Edit(Edit::Cut)),
+    ("paste", Command::Edit(Edit::Paste)),
+    ("indent", Command::Edit(Edit::Indent)),
+    ("dedent", Command::Edit(Edit::Dedent)),
+    ("save", Command::System(System::Save)),
+    ("quit", Command::System(System::Quit)),
+    ("show_line_numbers", Command::System(System::ShowLineNumbers)),
+    ("dismiss", Command::System(System::Dismiss)),
+    ("search", Command::System(System::Search)),
+    ("replace", Command::System(System::Replace)),
+    ("goto_line", Command::System(System::GotoLine)),
+    ("open", Command::System(System::Open)),
+    ("toggle_regex", Command::System(System::ToggleRegex)),
+    ("cycle_case_mode", Command::System(System::CycleCaseMode)),
+    ("toggle_whole_word", Command::System(System::ToggleWholeWord)),
+    ("toggle_line_ending", Command::System(System::ToggleLineEnding)),
+    ("reopen_with_encoding", Command::System(System::ReopenWithEncoding)),
+    ("save_with_encoding", Command::System(System::SaveWithEncoding)),
+    ("next_editor", Command::System(System::NextEditor)),
+    ("previous_editor", Command::System(System::PreviousEditor)),
+    ("new_editor", Command::System(System::NewEditor)),
+    ("save_all", Command::System(System::SaveAll)),
+    ("quit_all", Command::System(System::QuitAll)),
+];
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        COMMAND_NAMES
+            .iter()
+            .find(|(command_name, _)| *command_name == name)
+            .map(|(_, command)| *command)
+            .ok_or_else(|| format!("unknown command `{name}`"))
     }
 }
-
@@ -1,27 +1,62 @@
+use crate::disk_state::DiskState;
+use crate::line_ending::LineEnding;
+use crate::text_encoding::TextEncoding;
 use std::{
     fmt::{self, Display},
     path::PathBuf
 };
 
-#[derive(Default, Debug, Clone)]
+/// The path of a file and the details of its format that are restored on save.
+#[derive(Debug, Clone)]
 pub struct FileInfo {
-    pub path: Option<PathBuf>
+    pub path: Option<PathBuf>,
+    pub encoding: TextEncoding,
+    pub line_ending: LineEnding,
+    pub final_newline: bool,
+    /// The file starts with the byte order mark of its encoding.
+    pub bom: bool,
+    /// The file on disk when it was last read or written, `None` while there is none.
+    pub disk_state: Option<DiskState>,
+}
+
+impl Default for FileInfo {
+    fn default() -> Self {
+        Self {
+            path: None,
+            encoding: TextEncoding::default(),
+            line_ending: LineEnding::default(),
+            final_newline: true,
+            bom: false,
+            disk_state: None,
+        }
+    }
 }
 
 impl FileInfo {
     pub fn from(file_name: &str) -> Self {
         Self {
             path: Some(PathBuf::from(file_name)),
+            ..Self::default()
         }
     }
 
+    /// Short description of the format for the status bar, e.g. `UTF-8 BOM | CRLF`.
+    pub fn format_to_string(&self) -> String {
+        let mut format = self.encoding.to_string();
+        if self.bom {
+            format.push_str(" BOM");
+        }
+        format.push_str(" | ");
+        format.push_str(&self.line_ending.to_string());
+        if !self.final_newline {
+            format.push_str(" noeol");
+        }
+        format
+    }
+
     pub const fn has_path(&self) -> bool {
         self.path.is_some()
     }
-    
-    pub(crate) fn get_path(&self) -> Option<PathBuf> {
-        self.path.clone()
-    }
 }
 
 impl Display for FileInfo {
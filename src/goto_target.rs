@@ -0,0 +1,81 @@
+use std::str::FromStr;
+
+/// Destination typed in the go-to-line prompt. Line and column numbers are 1-based.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GotoTarget {
+    Absolute { line: usize, column: Option<usize> },
+    Relative(isize),
+    End,
+}
+
+impl FromStr for GotoTarget {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.trim();
+        let invalid = || format!("Invalid line number: {input}");
+
+        if input == "$" {
+            return Ok(Self::End);
+        }
+
+        if input.starts_with('+') || input.starts_with('-') {
+            return input
+                .parse::<isize>()
+                .map(Self::Relative)
+                .map_err(|_| invalid());
+        }
+
+        let (line, column) = match input.split_once(':') {
+            Some((line, column)) => (line, Some(column)),
+            None => (input, None),
+        };
+        let line = line.parse::<usize>().map_err(|_| invalid())?;
+        let column = column
+            .map(|column| column.parse::<usize>().map_err(|_| invalid()))
+            .transpose()?;
+
+        Ok(Self::Absolute { line, column })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_line_and_column() {
+        assert_eq!(
+            "12".parse(),
+            Ok(GotoTarget::Absolute {
+                line: 12,
+                column: None
+            })
+        );
+        assert_eq!(
+            " 12:7 ".parse(),
+            Ok(GotoTarget::Absolute {
+                line: 12,
+                column: Some(7)
+            })
+        );
+    }
+
+    #[test]
+    fn parses_relative_and_end() {
+        assert_eq!("+5".parse(), Ok(GotoTarget::Relative(5)));
+        assert_eq!("-3".parse(), Ok(GotoTarget::Relative(-3)));
+        assert_eq!("$".parse(), Ok(GotoTarget::End));
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        for input in ["", "abc", "12:", ":4", "1:x", "+", "+-2", "3.5"] {
+            assert_eq!(
+                input.parse::<GotoTarget>(),
+                Err(format!("Invalid line number: {input}")),
+                "{input:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,365 @@
+use crate::annotation::Annotation;
+use crate::annotation_type::AnnotationType;
+use crate::language::{Language, LineStart, Syntax};
+use crate::line::Line;
+use std::ops::Range;
+
+type ByteIndex = usize;
+type Span = (Range<ByteIndex>, AnnotationType);
+
+/// Where the tokenizer stands at the end of a line, so constructs spanning
+/// several lines carry over to the next one.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+enum LineState {
+    #[default]
+    Normal,
+    BlockComment {
+        depth: usize,
+    },
+    /// Index of the opening delimiter in `Syntax::multiline_quotes`.
+    MultilineString {
+        quote: usize,
+    },
+    /// Inside a fenced Markdown code block.
+    CodeBlock,
+}
+
+struct HighlightedLine {
+    text: String,
+    start_state: LineState,
+    end_state: LineState,
+    annotations: Vec<Annotation>,
+}
+
+#[derive(Default)]
+pub struct Highlighter {
+    language: Language,
+    lines: Vec<HighlightedLine>,
+}
+
+impl Highlighter {
+    pub const fn language(&self) -> Language {
+        self.language
+    }
+
+    pub fn set_language(&mut self, language: Language) {
+        if self.language != language {
+            self.language = language;
+            self.lines.clear();
+        }
+    }
+
+    /// Brings the highlighting of the first `count` lines up to date. A line is only
+    /// tokenized again when its text or the state left by the line above changed.
+    pub fn update(&mut self, lines: &[Line], count: usize) {
+        self.lines.truncate(lines.len());
+
+        let mut state = LineState::Normal;
+        for (index, line) in lines.iter().take(count).enumerate() {
+            if let Some(cached) = self.lines.get(index)
+                && cached.start_state == state
+                && cached.text == **line
+            {
+                state = cached.end_state;
+                continue;
+            }
+
+            let (spans, end_state) = tokenize(self.language, line, state);
+            let annotations = spans
+                .into_iter()
+                .map(|(range, kind)| Annotation {
+                    kind,
+                    start: line.byte_to_grapheme_idx(range.start),
+                    end: line.byte_to_grapheme_idx(range.end),
+                })
+                .collect();
+            let highlighted = HighlightedLine {
+                text: line.to_string(),
+                start_state: state,
+                end_state,
+                annotations,
+            };
+
+            if index < self.lines.len() {
+                self.lines[index] = highlighted;
+            } else {
+                self.lines.push(highlighted);
+            }
+            state = end_state;
+        }
+    }
+
+    pub fn annotations(&self, line_index: usize) -> &[Annotation] {
+        self.lines
+            .get(line_index)
+            .map_or(&[], |line| line.annotations.as_slice())
+    }
+}
+
+fn tokenize(language: Language, text: &str, state: LineState) -> (Vec<Span>, LineState) {
+    match (language, language.syntax()) {
+        (_, Some(syntax)) => tokenize_code(syntax, text, state),
+        (Language::Markdown, None) => tokenize_markdown(text, state),
+        _ => (Vec::new(), LineState::Normal),
+    }
+}
+
+fn tokenize_code(syntax: &Syntax, text: &str, mut state: LineState) -> (Vec<Span>, LineState) {
+    let mut spans = Vec::new();
+    let mut position = 0;
+
+    if state == LineState::Normal {
+        let indent = text.len().saturating_sub(text.trim_start().len());
+        let rest = &text[indent..];
+        match syntax.line_start {
+            LineStart::SectionHeader if rest.starts_with('[') => {
+                position = rest.find(']').map_or(text.len(), |end| indent + end + 1);
+                spans.push((indent..position, AnnotationType::Type));
+            }
+            LineStart::Directive if rest.starts_with('#') => {
+                position = indent + 1 + identifier_len(&rest[1..]);
+                spans.push((indent..position, AnnotationType::Keyword));
+            }
+            _ => {}
+        }
+    }
+
+    while position < text.len() {
+        let (span, end, next_state) = match state {
+            LineState::BlockComment { depth } => {
+                let (end, next_state) = scan_block_comment(syntax, text, position, depth);
+                (
+                    Some((position..end, AnnotationType::Comment)),
+                    end,
+                    next_state,
+                )
+            }
+            LineState::MultilineString { quote } => {
+                let delimiter = syntax.multiline_quotes.get(quote).copied().unwrap_or("\"");
+                find_closing(&text[position..], delimiter).map_or_else(
+                    || {
+                        (
+                            Some((position..text.len(), AnnotationType::String)),
+                            text.len(),
+                            state,
+                        )
+                    },
+                    |end| {
+                        let end = position + end;
+                        (
+                            Some((position..end, AnnotationType::String)),
+                            end,
+                            LineState::Normal,
+                        )
+                    },
+                )
+            }
+            LineState::Normal | LineState::CodeBlock => next_token(syntax, text, position),
+        };
+        spans.extend(span);
+        position = end;
+        state = next_state;
+    }
+
+    (spans, state)
+}
+
+/// Scans the token starting at `position`, returning its span if it is highlighted,
+/// where it ends and the state it leaves the tokenizer in.
+fn next_token(
+    syntax: &Syntax,
+    text: &str,
+    position: ByteIndex,
+) -> (Option<Span>, ByteIndex, LineState) {
+    let rest = &text[position..];
+    let token = |end, kind| (Some((position..end, kind)), end, LineState::Normal);
+
+    if let Some(comment) = syntax.line_comment
+        && rest.starts_with(comment)
+    {
+        return token(text.len(), AnnotationType::Comment);
+    }
+
+    if let Some((open, _)) = syntax.block_comment
+        && rest.starts_with(open)
+    {
+        let end = position + open.len();
+        let state = LineState::BlockComment { depth: 1 };
+        return (Some((position..end, AnnotationType::Comment)), end, state);
+    }
+
+    if let Some(quote) = syntax
+        .multiline_quotes
+        .iter()
+        .position(|delimiter| rest.starts_with(delimiter))
+    {
+        let end = position + syntax.multiline_quotes[quote].len();
+        let state = LineState::MultilineString { quote };
+        return (Some((position..end, AnnotationType::String)), end, state);
+    }
+
+    if syntax.lifetimes && rest.starts_with('\'') && !is_char_literal(rest) {
+        return token(
+            position + 1 + identifier_len(&rest[1..]),
+            AnnotationType::Type,
+        );
+    }
+
+    if let Some(delimiter) = syntax
+        .quotes
+        .iter()
+        .find(|delimiter| rest.starts_with(**delimiter))
+    {
+        let start = position + delimiter.len();
+        let end = find_closing(&text[start..], delimiter).map_or(text.len(), |end| start + end);
+        return token(end, AnnotationType::String);
+    }
+
+    let Some(character) = rest.chars().next() else {
+        return (None, text.len(), LineState::Normal);
+    };
+
+    if character.is_ascii_digit() {
+        return token(position + number_len(rest), AnnotationType::Number);
+    }
+
+    if character.is_alphabetic() || character == '_' {
+        let end = position + identifier_len(rest);
+        let word = &text[position..end];
+        if syntax.keywords.contains(&word) {
+            return token(end, AnnotationType::Keyword);
+        }
+        if syntax.types.contains(&word) || (syntax.capitalized_types && character.is_uppercase()) {
+            return token(end, AnnotationType::Type);
+        }
+        return (None, end, LineState::Normal);
+    }
+
+    (None, position + character.len_utf8(), LineState::Normal)
+}
+
+fn tokenize_markdown(text: &str, state: LineState) -> (Vec<Span>, LineState) {
+    let whole_line = |kind| vec![(0..text.len(), kind)];
+    let trimmed = text.trim_start();
+    let indent = text.len().saturating_sub(trimmed.len());
+
+    if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+        let next_state = if state == LineState::CodeBlock {
+            LineState::Normal
+        } else {
+            LineState::CodeBlock
+        };
+        return (whole_line(AnnotationType::String), next_state);
+    }
+    if state == LineState::CodeBlock {
+        return (whole_line(AnnotationType::String), state);
+    }
+    if trimmed.starts_with('#') {
+        return (whole_line(AnnotationType::Keyword), LineState::Normal);
+    }
+    if trimmed.starts_with('>') {
+        return (whole_line(AnnotationType::Comment), LineState::Normal);
+    }
+
+    let mut spans = Vec::new();
+    let digits = trimmed.len()
+        - trimmed
+            .trim_start_matches(|c: char| c.is_ascii_digit())
+            .len();
+    let marker_len = if ["- ", "* ", "+ "]
+        .iter()
+        .any(|marker| trimmed.starts_with(marker))
+    {
+        1
+    } else if digits > 0 && trimmed[digits..].starts_with(". ") {
+        digits + 1
+    } else {
+        0
+    };
+    if marker_len > 0 {
+        spans.push((indent..indent + marker_len, AnnotationType::Keyword));
+    }
+
+    let mut position = indent + marker_len;
+    while let Some(start) = text[position..].find('`').map(|start| position + start) {
+        let end = text[start + 1..]
+            .find('`')
+            .map_or(text.len(), |end| start + end + 2);
+        spans.push((start..end, AnnotationType::String));
+        position = end;
+    }
+
+    (spans, LineState::Normal)
+}
+
+/// Returns the position right after the comment closing at or after `position`.
+fn scan_block_comment(
+    syntax: &Syntax,
+    text: &str,
+    position: ByteIndex,
+    mut depth: usize,
+) -> (ByteIndex, LineState) {
+    let Some((open, close)) = syntax.block_comment else {
+        return (text.len(), LineState::Normal);
+    };
+
+    let mut index = position;
+    while index < text.len() {
+        let rest = &text[index..];
+        if rest.starts_with(close) {
+            index += close.len();
+            depth = depth.saturating_sub(1);
+            if depth == 0 {
+                return (index, LineState::Normal);
+            }
+        } else if syntax.nested_comments && rest.starts_with(open) {
+            index += open.len();
+            depth = depth.saturating_add(1);
+        } else {
+            index += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    (text.len(), LineState::BlockComment { depth })
+}
+
+/// Returns the length of `text` up to and including the first unescaped `delimiter`.
+fn find_closing(text: &str, delimiter: &str) -> Option<ByteIndex> {
+    let mut characters = text.char_indices();
+    while let Some((index, character)) = characters.next() {
+        if character == '\\' {
+            characters.next();
+        } else if text[index..].starts_with(delimiter) {
+            return Some(index + delimiter.len());
+        }
+    }
+    None
+}
+
+fn is_char_literal(text: &str) -> bool {
+    let mut characters = text.chars().skip(1);
+    match characters.next() {
+        Some('\\') => true,
+        Some(_) => characters.next() == Some('\''),
+        None => false,
+    }
+}
+
+fn identifier_len(text: &str) -> ByteIndex {
+    text.find(|character: char| !character.is_alphanumeric() && character != '_')
+        .unwrap_or(text.len())
+}
+
+fn number_len(text: &str) -> ByteIndex {
+    let mut previous = '0';
+    text.char_indices()
+        .find(|&(index, character)| {
+            let is_part = character.is_ascii_alphanumeric()
+                || character == '_'
+                || (character == '.'
+                    && previous.is_ascii_digit()
+                    && text[index + 1..].starts_with(|next: char| next.is_ascii_digit()));
+            previous = character;
+            !is_part
+        })
+        .map_or(text.len(), |(index, _)| index)
+}
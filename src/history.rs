@@ -0,0 +1,388 @@
+use crate::line::Line;
+use crate::location::Location;
+use std::collections::VecDeque;
+
+const MAX_UNDO_STEPS: usize = 1000;
+
+/// A single reversible modification of a `Buffer`.
+/// `text` may span several lines, in which case the lines are separated by `'\n'`.
+#[derive(Clone, Debug)]
+pub enum Change {
+    Insert { at: Location, text: String },
+    Delete { at: Location, text: String },
+    InsertLine { index: usize, text: String },
+    RemoveLine { index: usize, text: String },
+}
+
+impl Change {
+    pub fn invert(&self) -> Self {
+        match self {
+            Self::Insert { at, text } => Self::Delete {
+                at: *at,
+                text: text.clone(),
+            },
+            Self::Delete { at, text } => Self::Insert {
+                at: *at,
+                text: text.clone(),
+            },
+            Self::InsertLine { index, text } => Self::RemoveLine {
+                index: *index,
+                text: text.clone(),
+            },
+            Self::RemoveLine { index, text } => Self::InsertLine {
+                index: *index,
+                text: text.clone(),
+            },
+        }
+    }
+
+    pub const fn start(&self) -> Location {
+        match self {
+            Self::Insert { at, .. } | Self::Delete { at, .. } => *at,
+            Self::InsertLine { index, .. } | Self::RemoveLine { index, .. } => Location {
+                line_index: *index,
+                grapheme_index: 0,
+            },
+        }
+    }
+
+    /// Location right after the text touched by this change.
+    pub fn end(&self) -> Location {
+        match self {
+            Self::Insert { at, text } | Self::Delete { at, text } => end_of_text(*at, text),
+            Self::InsertLine { index, text } | Self::RemoveLine { index, text } => Location {
+                line_index: *index,
+                grapheme_index: Line::from(text).grapheme_count(),
+            },
+        }
+    }
+}
+
+pub fn end_of_text(at: Location, text: &str) -> Location {
+    let line_count = text.split('\n').count();
+    let last = text.rsplit('\n').next().unwrap_or_default();
+    let last_width = Line::from(last).grapheme_count();
+
+    if line_count > 1 {
+        Location {
+            line_index: at.line_index.saturating_add(line_count - 1),
+            grapheme_index: last_width,
+        }
+    } else {
+        Location {
+            line_index: at.line_index,
+            grapheme_index: at.grapheme_index.saturating_add(last_width),
+        }
+    }
+}
+
+struct Transaction {
+    /// Identifies the text after this step, to recognize the saved text again.
+    id: u64,
+    changes: Vec<Change>,
+    is_typing: bool,
+}
+
+pub struct History {
+    undo_stack: VecDeque<Transaction>,
+    redo_stack: Vec<Transaction>,
+    group_depth: usize,
+    sealed: bool,
+    revision: u64,
+    last_id: u64,
+    /// Identifies the text before the oldest step that can be undone.
+    base_id: u64,
+    /// Identifies the text that was last saved, `None` once it can not be reached.
+    saved_id: Option<u64>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self {
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+            group_depth: 0,
+            sealed: false,
+            revision: 0,
+            last_id: 0,
+            base_id: 0,
+            saved_id: Some(0),
+        }
+    }
+}
+
+impl History {
+    pub fn record(&mut self, change: Change) {
+        self.redo_stack.clear();
+        self.revision += 1;
+
+        if self.group_depth > 0
+            && let Some(transaction) = self.undo_stack.back_mut()
+        {
+            transaction.changes.push(change);
+            return;
+        }
+
+        self.push(vec![change], false);
+        self.sealed = true;
+    }
+
+    /// Records a typed character, merging it into the previous step when
+    /// the caret has not moved away from the end of the text typed so far.
+    pub fn record_typing(&mut self, change: Change) {
+        self.redo_stack.clear();
+        self.revision += 1;
+
+        if self.group_depth > 0 {
+            self.record(change);
+            return;
+        }
+
+        let extends_last = !self.sealed
+            && self.undo_stack.back().is_some_and(|transaction| {
+                transaction.is_typing
+                    && transaction
+                        .changes
+                        .last()
+                        .is_some_and(|last| last.end() == change.start())
+            });
+
+        if extends_last {
+            if let Some(transaction) = self.undo_stack.back_mut() {
+                transaction.changes.push(change);
+            }
+            return;
+        }
+
+        self.push(vec![change], true);
+        self.sealed = false;
+    }
+
+    /// Every change recorded until the matching `end_group` is undone as a single step.
+    pub fn begin_group(&mut self) {
+        if self.group_depth == 0 {
+            self.push(Vec::new(), false);
+        }
+        self.group_depth = self.group_depth.saturating_add(1);
+    }
+
+    pub fn end_group(&mut self) {
+        self.group_depth = self.group_depth.saturating_sub(1);
+        if self.group_depth > 0 {
+            return;
+        }
+
+        if self
+            .undo_stack
+            .back()
+            .is_some_and(|transaction| transaction.changes.is_empty())
+        {
+            self.undo_stack.pop_back();
+        }
+        self.sealed = true;
+    }
+
+    pub fn pop_undo(&mut self) -> Option<Vec<Change>> {
+        self.sealed = true;
+        let transaction = self.undo_stack.pop_back()?;
+        self.revision += 1;
+        let changes = transaction.changes.clone();
+        self.redo_stack.push(transaction);
+        Some(changes)
+    }
+
+    pub fn pop_redo(&mut self) -> Option<Vec<Change>> {
+        self.sealed = true;
+        let transaction = self.redo_stack.pop()?;
+        self.revision += 1;
+        let changes = transaction.changes.clone();
+        self.undo_stack.push_back(transaction);
+        Some(changes)
+    }
+
+    /// Changes whenever the text is edited, undone or redone.
+    pub const fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Remembers the current text as saved. Typing afterwards starts a new step, so
+    /// undoing it returns to exactly the saved text.
+    pub fn mark_saved(&mut self) {
+        self.saved_id = Some(self.current_id());
+        self.sealed = true;
+    }
+
+    /// Called when the file changes in a way undo can not revert, e.g. its line ending.
+    pub const fn forget_saved(&mut self) {
+        self.saved_id = None;
+    }
+
+    /// True when undo and redo led back to the text that was last saved.
+    pub fn is_saved(&self) -> bool {
+        self.saved_id == Some(self.current_id())
+    }
+
+    fn current_id(&self) -> u64 {
+        self.undo_stack
+            .back()
+            .map_or(self.base_id, |transaction| transaction.id)
+    }
+
+    fn push(&mut self, changes: Vec<Change>, is_typing: bool) {
+        if self.undo_stack.len() >= MAX_UNDO_STEPS
+            && let Some(oldest) = self.undo_stack.pop_front()
+        {
+            self.base_id = oldest.id;
+        }
+        self.last_id += 1;
+        self.undo_stack.push_back(Transaction {
+            id: self.last_id,
+            changes,
+            is_typing,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert(line_index: usize, grapheme_index: usize, text: &str) -> Change {
+        Change::Insert {
+            at: Location {
+                line_index,
+                grapheme_index,
+            },
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn typing_at_the_end_is_one_step() {
+        let mut history = History::default();
+        history.record_typing(insert(0, 0, "a"));
+        history.record_typing(insert(0, 1, "b"));
+        history.record_typing(insert(0, 5, "c"));
+
+        assert_eq!(history.pop_undo().map(|changes| changes.len()), Some(1));
+        assert_eq!(history.pop_undo().map(|changes| changes.len()), Some(2));
+        assert!(history.pop_undo().is_none());
+    }
+
+    #[test]
+    fn recorded_change_ends_typing() {
+        let mut history = History::default();
+        history.record_typing(insert(0, 0, "a"));
+        history.record(insert(0, 1, "b"));
+        history.record_typing(insert(0, 2, "c"));
+
+        assert_eq!(history.pop_undo().map(|changes| changes.len()), Some(1));
+        assert_eq!(history.pop_undo().map(|changes| changes.len()), Some(1));
+    }
+
+    #[test]
+    fn group_is_one_step() {
+        let mut history = History::default();
+        history.begin_group();
+        history.record(insert(0, 0, "a"));
+        history.begin_group();
+        history.record_typing(insert(0, 1, "b"));
+        history.end_group();
+        history.record(insert(1, 0, "c"));
+        history.end_group();
+
+        assert_eq!(history.pop_undo().map(|changes| changes.len()), Some(3));
+        assert!(history.pop_undo().is_none());
+    }
+
+    #[test]
+    fn empty_group_leaves_no_step() {
+        let mut history = History::default();
+        history.begin_group();
+        history.end_group();
+
+        assert!(history.pop_undo().is_none());
+        assert!(history.is_saved());
+    }
+
+    #[test]
+    fn revision_changes_with_the_text() {
+        let mut history = History::default();
+        let start = history.revision();
+        history.record_typing(insert(0, 0, "a"));
+        let typed = history.revision();
+        history.record_typing(insert(0, 1, "b"));
+        assert!(start != typed && typed != history.revision());
+
+        let before_undo = history.revision();
+        history.pop_undo();
+        assert_ne!(history.revision(), before_undo);
+        let unchanged = history.revision();
+        history.pop_undo();
+        assert_eq!(history.revision(), unchanged);
+    }
+
+    #[test]
+    fn new_change_clears_redo() {
+        let mut history = History::default();
+        history.record(insert(0, 0, "a"));
+        history.pop_undo();
+        history.record(insert(0, 0, "b"));
+
+        assert!(history.pop_redo().is_none());
+    }
+
+    #[test]
+    fn oldest_steps_are_dropped_at_the_limit() {
+        let mut history = History::default();
+        for index in 0..=MAX_UNDO_STEPS {
+            history.record(insert(0, index, "a"));
+        }
+
+        let mut steps = 0;
+        while history.pop_undo().is_some() {
+            steps += 1;
+        }
+        assert_eq!(steps, MAX_UNDO_STEPS);
+    }
+
+    #[test]
+    fn undo_and_redo_find_the_saved_text() {
+        let mut history = History::default();
+        assert!(history.is_saved());
+        history.record_typing(insert(0, 0, "a"));
+        history.mark_saved();
+        history.record_typing(insert(0, 1, "b"));
+        assert!(!history.is_saved());
+
+        history.pop_undo();
+        assert!(history.is_saved());
+        history.pop_undo();
+        assert!(!history.is_saved());
+        history.pop_redo();
+        assert!(history.is_saved());
+    }
+
+    #[test]
+    fn saved_text_is_lost_when_redo_is_cleared() {
+        let mut history = History::default();
+        history.record(insert(0, 0, "a"));
+        history.mark_saved();
+        history.pop_undo();
+        history.record(insert(0, 0, "b"));
+        history.pop_undo();
+
+        assert!(!history.is_saved());
+    }
+
+    #[test]
+    fn dropped_steps_do_not_look_saved() {
+        let mut history = History::default();
+        for index in 0..=MAX_UNDO_STEPS {
+            history.record(insert(0, index, "a"));
+        }
+        while history.pop_undo().is_some() {}
+
+        assert!(!history.is_saved());
+    }
+}
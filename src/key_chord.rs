@@ -0,0 +1,256 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+const MODIFIERS: KeyModifiers = KeyModifiers::CONTROL
+    .union(KeyModifiers::ALT)
+    .union(KeyModifiers::SHIFT);
+
+const NAMED_KEYS: &[(&str, KeyCode)] = &[
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("Enter", KeyCode::Enter),
+    ("Tab", KeyCode::Tab),
+    ("Backspace", KeyCode::Backspace),
+    ("Delete", KeyCode::Delete),
+    ("Esc", KeyCode::Esc),
+];
+
+/// A single key press with its modifiers, e.g. `Ctrl-K` or `Shift-Up`.
+///
+/// Letters are stored in lowercase with `SHIFT` marking capitals, so that the
+/// chords parsed from the config match the events reported by the terminal.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let mut modifiers = modifiers.intersection(MODIFIERS);
+        let code = match code {
+            KeyCode::Char(c) if c.is_alphabetic() => {
+                if c.is_uppercase() {
+                    modifiers.insert(KeyModifiers::SHIFT);
+                }
+                KeyCode::Char(c.to_lowercase().next().unwrap_or(c))
+            }
+            KeyCode::Char(_) | KeyCode::BackTab => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                code
+            }
+            _ => code,
+        };
+        Self { code, modifiers }
+    }
+
+    /// The same key without any modifier.
+    pub fn without_modifiers(self) -> Self {
+        Self::new(self.code, KeyModifiers::NONE)
+    }
+
+    fn parse_key(name: &str, has_modifiers: bool) -> Option<KeyCode> {
+        if let Some((_, code)) = NAMED_KEYS
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        {
+            return Some(*code);
+        }
+        if name.eq_ignore_ascii_case("Space") {
+            return Some(KeyCode::Char(' '));
+        }
+        if let Some(number) = name.strip_prefix(['F', 'f'])
+            && let Ok(number) = number.parse::<u8>()
+            && (1..=12).contains(&number)
+        {
+            return Some(KeyCode::F(number));
+        }
+
+        let mut characters = name.chars();
+        match (characters.next(), characters.next()) {
+            // With Ctrl or Alt, `Ctrl-K` and `Ctrl-k` are the same key, use `Shift` for capitals.
+            (Some(c), None) if has_modifiers => Some(KeyCode::Char(c.to_ascii_lowercase())),
+            (Some(c), None) => Some(KeyCode::Char(c)),
+            _ => None,
+        }
+    }
+}
+
+impl From<KeyEvent> for KeyChord {
+    fn from(event: KeyEvent) -> Self {
+        Self::new(event.code, event.modifiers)
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = String;
+
+    fn from_str(chord: &str) -> Result<Self, Self::Err> {
+        let (modifier_names, key) = if chord == "-" {
+            ("", "-")
+        } else if let Some(modifier_names) = chord.strip_suffix("--") {
+            (modifier_names, "-")
+        } else {
+            chord.rsplit_once('-').unwrap_or(("", chord))
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for name in modifier_names.split('-').filter(|name| !name.is_empty()) {
+            modifiers |= match name.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier `{name}` in `{chord}`")),
+            };
+        }
+
+        let has_modifiers = modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        let code = match Self::parse_key(key, has_modifiers) {
+            Some(KeyCode::Tab) if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+            Some(code) => code,
+            None => return Err(format!("unknown key `{key}` in `{chord}`")),
+        };
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl Display for KeyChord {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let KeyCode::Char(c) = self.code
+            && self.modifiers == KeyModifiers::SHIFT
+        {
+            return write!(formatter, "{}", c.to_uppercase());
+        }
+
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "Ctrl-"),
+            (KeyModifiers::ALT, "Alt-"),
+            (KeyModifiers::SHIFT, "Shift-"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(formatter, "{name}")?;
+            }
+        }
+
+        match self.code {
+            KeyCode::BackTab => write!(formatter, "Shift-Tab"),
+            KeyCode::Char(' ') => write!(formatter, "Space"),
+            KeyCode::Char(c) if !self.modifiers.is_empty() => {
+                write!(formatter, "{}", c.to_ascii_uppercase())
+            }
+            KeyCode::Char(c) => write!(formatter, "{c}"),
+            KeyCode::F(number) => write!(formatter, "F{number}"),
+            code => {
+                let name = NAMED_KEYS
+                    .iter()
+                    .find(|(_, key)| *key == code)
+                    .map_or("?", |(name, _)| name);
+                write!(formatter, "{name}")
+            }
+        }
+    }
+}
+
+/// Parses a space separated sequence of chords, e.g. `Ctrl-K Ctrl-C`.
+pub fn parse_sequence(sequence: &str) -> Result<Vec<KeyChord>, String> {
+    let chords = sequence
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<Vec<KeyChord>, String>>()?;
+    if chords.is_empty() {
+        return Err(String::from("empty key binding"));
+    }
+    Ok(chords)
+}
+
+pub fn format_sequence(sequence: &[KeyChord]) -> String {
+    sequence
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(code: KeyCode, modifiers: KeyModifiers) -> KeyChord {
+        KeyChord::new(code, modifiers)
+    }
+
+    #[test]
+    fn parses_modifiers_and_named_keys() {
+        assert_eq!(
+            "Ctrl-k".parse(),
+            Ok(chord(KeyCode::Char('k'), KeyModifiers::CONTROL))
+        );
+        assert_eq!(
+            "control-alt-Delete".parse(),
+            Ok(chord(
+                KeyCode::Delete,
+                KeyModifiers::CONTROL | KeyModifiers::ALT
+            ))
+        );
+        assert_eq!("F5".parse(), Ok(chord(KeyCode::F(5), KeyModifiers::NONE)));
+        assert_eq!(
+            "Space".parse(),
+            Ok(chord(KeyCode::Char(' '), KeyModifiers::NONE))
+        );
+        assert_eq!(
+            "Shift-Tab".parse(),
+            Ok(chord(KeyCode::BackTab, KeyModifiers::NONE))
+        );
+    }
+
+    #[test]
+    fn parses_the_dash_key() {
+        assert_eq!(
+            "-".parse(),
+            Ok(chord(KeyCode::Char('-'), KeyModifiers::NONE))
+        );
+        assert_eq!(
+            "Ctrl--".parse(),
+            Ok(chord(KeyCode::Char('-'), KeyModifiers::CONTROL))
+        );
+    }
+
+    #[test]
+    fn capitals_match_terminal_events() {
+        let shifted = chord(KeyCode::Char('K'), KeyModifiers::SHIFT);
+        assert_eq!("K".parse(), Ok(shifted));
+        assert_eq!("Shift-k".parse(), Ok(shifted));
+        assert_eq!(
+            "Ctrl-K".parse(),
+            Ok(chord(KeyCode::Char('k'), KeyModifiers::CONTROL))
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_names() {
+        assert_eq!(
+            "Hyper-K".parse::<KeyChord>(),
+            Err(String::from("unknown modifier `Hyper` in `Hyper-K`"))
+        );
+        assert_eq!(
+            "Ctrl-Foo".parse::<KeyChord>(),
+            Err(String::from("unknown key `Foo` in `Ctrl-Foo`"))
+        );
+        assert_eq!("F13".parse::<KeyChord>().ok(), None);
+    }
+
+    #[test]
+    fn sequences_round_trip() {
+        let sequence = parse_sequence("Ctrl-K  Ctrl-C").unwrap();
+        assert_eq!(sequence.len(), 2);
+        assert_eq!(format_sequence(&sequence), "Ctrl-K Ctrl-C");
+        assert_eq!(parse_sequence("  "), Err(String::from("empty key binding")));
+    }
+}
@@ -0,0 +1,288 @@
+use crate::editor_commands::{Command, Edit};
+use crate::key_chord::{KeyChord, format_sequence, parse_sequence};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::{BTreeMap, HashMap};
+
+/// The bindings used when the config file does not override them.
+const DEFAULT_BINDINGS: &[(&str, &str)] = &[
+    ("Up", "move_up"),
+    ("Down", "move_down"),
+    ("Left", "move_left"),
+    ("Right", "move_right"),
+    ("PageUp", "page_up"),
+    ("PageDown", "page_down"),
+    ("Home", "home"),
+    ("End", "end"),
+    ("Ctrl-Right", "word_jump_right"),
+    ("Ctrl-Left", "word_jump_left"),
+    ("Shift-Up", "select_up"),
+    ("Shift-Down", "select_down"),
+    ("Shift-Left", "select_left"),
+    ("Shift-Right", "select_right"),
+    ("Shift-PageUp", "select_page_up"),
+    ("Shift-PageDown", "select_page_down"),
+    ("Shift-Home", "select_home"),
+    ("Shift-End", "select_end"),
+    ("Ctrl-Shift-Right", "select_word_jump_right"),
+    ("Ctrl-Shift-Left", "select_word_jump_left"),
+    ("Tab", "insert_tab"),
+    ("Delete", "delete"),
+    ("Enter", "enter"),
+    ("Backspace", "backspace"),
+    ("Shift-Tab", "dedent"),
+    ("Alt-.", "indent"),
+    ("Alt-,", "dedent"),
+    ("Ctrl-Z", "undo"),
+    ("Ctrl-Y", "redo"),
+    ("Ctrl-C", "copy"),
+    ("Ctrl-X", "cut"),
+    ("Ctrl-V", "paste"),
+    ("Ctrl-S", "save"),
+    ("Ctrl-Q", "quit"),
+    ("Ctrl-L", "show_line_numbers"),
+    ("Esc", "dismiss"),
+    ("Ctrl-F", "search"),
+    ("Ctrl-R", "replace"),
+    ("Ctrl-G", "goto_line"),
+    ("Ctrl-E", "open"),
+    ("Alt-R", "toggle_regex"),
+    ("Alt-C", "cycle_case_mode"),
+    ("Alt-W", "toggle_whole_word"),
+    ("Alt-E", "toggle_line_ending"),
+    ("Alt-O", "reopen_with_encoding"),
+    ("Alt-S", "save_with_encoding"),
+    ("Ctrl-P", "next_editor"),
+    ("Ctrl-O", "previous_editor"),
+    ("Ctrl-N", "new_editor"),
+    ("Ctrl-K Ctrl-S", "save_all"),
+    ("Ctrl-K Ctrl-Q", "quit_all"),
+];
+
+/// Binding a key sequence to this name removes its default binding.
+const UNBIND: &str = "none";
+
+pub enum KeyResolution {
+    Command(Command),
+    /// The keys pressed so far start a longer sequence.
+    Pending(String),
+    Unbound,
+}
+
+/// Maps key sequences to commands. Keys that are not bound and print a
+/// character insert it.
+#[derive(Default)]
+pub struct Keymap {
+    bindings: HashMap<Vec<KeyChord>, Command>,
+    pending: Vec<KeyChord>,
+}
+
+impl Keymap {
+    /// Builds the default keymap with the bindings of the config file on top of it.
+    /// Returns the keymap and a warning for every binding that could not be applied.
+    pub fn new(overrides: &BTreeMap<String, String>) -> (Self, Vec<String>) {
+        let mut keymap = Self::default();
+        for (keys, name) in DEFAULT_BINDINGS {
+            if let (Ok(sequence), Ok(command)) = (parse_sequence(keys), name.parse()) {
+                keymap.bindings.insert(sequence, command);
+            }
+        }
+
+        let mut warnings = Vec::new();
+        let mut overridden: Vec<Vec<KeyChord>> = Vec::new();
+        for (keys, name) in overrides {
+            let sequence = match parse_sequence(keys) {
+                Ok(sequence) => sequence,
+                Err(error) => {
+                    warnings.push(format!("Ignoring key binding `{keys}`: {error}"));
+                    continue;
+                }
+            };
+            if name == UNBIND {
+                keymap.bindings.remove(&sequence);
+                continue;
+            }
+            let command = match name.parse() {
+                Ok(command) => command,
+                Err(error) => {
+                    warnings.push(format!("Ignoring key binding `{keys}`: {error}"));
+                    continue;
+                }
+            };
+
+            if let Some(other) = overridden
+                .iter()
+                .find(|other| **other == sequence || conflicts(other, &sequence))
+            {
+                warnings.push(format!(
+                    "Key binding `{}` conflicts with `{}` and is ignored",
+                    format_sequence(&sequence),
+                    format_sequence(other)
+                ));
+                continue;
+            }
+
+            let shadowed: Vec<Vec<KeyChord>> = keymap
+                .bindings
+                .keys()
+                .filter(|other| conflicts(other, &sequence))
+                .cloned()
+                .collect();
+            for other in shadowed {
+                warnings.push(format!(
+                    "Key binding `{}` replaces the conflicting default `{}`",
+                    format_sequence(&sequence),
+                    format_sequence(&other)
+                ));
+                keymap.bindings.remove(&other);
+            }
+
+            keymap.bindings.insert(sequence.clone(), command);
+            overridden.push(sequence);
+        }
+
+        (keymap, warnings)
+    }
+
+    /// Feeds a key press into the keymap, returning the command once a bound
+    /// sequence is complete.
+    pub fn resolve(&mut self, event: KeyEvent) -> KeyResolution {
+        let chord = KeyChord::from(event);
+        self.pending.push(chord);
+
+        if let Some(command) = self.bindings.get(&self.pending) {
+            self.pending.clear();
+            return KeyResolution::Command(*command);
+        }
+        if self
+            .bindings
+            .keys()
+            .any(|sequence| is_prefix(&self.pending, sequence))
+        {
+            return KeyResolution::Pending(format_sequence(&self.pending));
+        }
+
+        let sequence = std::mem::take(&mut self.pending);
+        if sequence.len() > 1 {
+            return KeyResolution::Unbound;
+        }
+
+        match (event.code, chord.modifiers) {
+            (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+                KeyResolution::Command(Command::Edit(Edit::Insert(c)))
+            }
+            (KeyCode::Char(_), _) => KeyResolution::Unbound,
+            // Special keys keep their meaning when pressed with modifiers nothing is bound to.
+            _ => self
+                .bindings
+                .get(&[chord.without_modifiers()][..])
+                .map_or(KeyResolution::Unbound, |command| {
+                    KeyResolution::Command(*command)
+                }),
+        }
+    }
+}
+
+/// Whether `prefix` is a strict prefix of `sequence`.
+fn is_prefix(prefix: &[KeyChord], sequence: &[KeyChord]) -> bool {
+    prefix.len() < sequence.len() && sequence.starts_with(prefix)
+}
+
+/// Two sequences conflict when one of them can never be typed because the other is
+/// its prefix.
+fn conflicts(first: &[KeyChord], second: &[KeyChord]) -> bool {
+    is_prefix(first, second) || is_prefix(second, first)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor_commands::System;
+
+    fn keymap(overrides: &[(&str, &str)]) -> (Keymap, Vec<String>) {
+        let overrides = overrides
+            .iter()
+            .map(|(keys, name)| ((*keys).to_string(), (*name).to_string()))
+            .collect();
+        Keymap::new(&overrides)
+    }
+
+    fn press(keymap: &mut Keymap, code: KeyCode, modifiers: KeyModifiers) -> KeyResolution {
+        keymap.resolve(KeyEvent::new(code, modifiers))
+    }
+
+    #[test]
+    fn resolves_multi_key_sequences() {
+        let (mut keymap, warnings) = keymap(&[("Ctrl-K Ctrl-S", "save")]);
+        assert!(warnings.is_empty());
+
+        let first = press(&mut keymap, KeyCode::Char('k'), KeyModifiers::CONTROL);
+        assert!(matches!(first, KeyResolution::Pending(keys) if keys == "Ctrl-K"));
+        let second = press(&mut keymap, KeyCode::Char('s'), KeyModifiers::CONTROL);
+        assert!(matches!(
+            second,
+            KeyResolution::Command(Command::System(System::Save))
+        ));
+    }
+
+    #[test]
+    fn unfinished_sequence_is_unbound() {
+        let (mut keymap, _) = keymap(&[("Ctrl-K Ctrl-S", "save")]);
+
+        press(&mut keymap, KeyCode::Char('k'), KeyModifiers::CONTROL);
+        let resolution = press(&mut keymap, KeyCode::Char('x'), KeyModifiers::NONE);
+        assert!(matches!(resolution, KeyResolution::Unbound));
+        let next = press(&mut keymap, KeyCode::Char('x'), KeyModifiers::NONE);
+        assert!(matches!(
+            next,
+            KeyResolution::Command(Command::Edit(Edit::Insert('x')))
+        ));
+    }
+
+    #[test]
+    fn override_replaces_conflicting_default() {
+        let (mut keymap, warnings) = keymap(&[("Ctrl-S Ctrl-S", "save")]);
+
+        assert_eq!(
+            warnings,
+            ["Key binding `Ctrl-S Ctrl-S` replaces the conflicting default `Ctrl-S`"]
+        );
+        let resolution = press(&mut keymap, KeyCode::Char('s'), KeyModifiers::CONTROL);
+        assert!(matches!(resolution, KeyResolution::Pending(_)));
+    }
+
+    #[test]
+    fn conflicting_overrides_keep_the_first() {
+        let (mut keymap, warnings) = keymap(&[("Ctrl-T", "copy"), ("Ctrl-T Ctrl-C", "cut")]);
+
+        assert_eq!(
+            warnings,
+            ["Key binding `Ctrl-T Ctrl-C` conflicts with `Ctrl-T` and is ignored"]
+        );
+        let resolution = press(&mut keymap, KeyCode::Char('t'), KeyModifiers::CONTROL);
+        assert!(matches!(
+            resolution,
+            KeyResolution::Command(Command::Edit(Edit::Copy))
+        ));
+    }
+
+    #[test]
+    fn invalid_overrides_are_reported() {
+        let (_, warnings) = keymap(&[("Ctrl-Foo", "copy"), ("Ctrl-J", "fly")]);
+
+        assert_eq!(
+            warnings,
+            [
+                "Ignoring key binding `Ctrl-Foo`: unknown key `Foo` in `Ctrl-Foo`",
+                "Ignoring key binding `Ctrl-J`: unknown command `fly`",
+            ]
+        );
+    }
+
+    #[test]
+    fn none_removes_a_default() {
+        let (mut keymap, _) = keymap(&[("Ctrl-S", UNBIND)]);
+
+        let resolution = press(&mut keymap, KeyCode::Char('s'), KeyModifiers::CONTROL);
+        assert!(matches!(resolution, KeyResolution::Unbound));
+    }
+}
@@ -0,0 +1,183 @@
+use std::fmt::{self, Display};
+use std::path::Path;
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Language {
+    #[default]
+    PlainText,
+    Rust,
+    C,
+    Python,
+    Toml,
+    Markdown,
+    Json,
+}
+
+/// Constructs recognized only at the start of a line.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LineStart {
+    Plain,
+    /// `[table]` headers, as in TOML.
+    SectionHeader,
+    /// `#include` style preprocessor directives, as in C.
+    Directive,
+}
+
+/// Describes the tokens of a programming language well enough to color them.
+pub struct Syntax {
+    pub keywords: &'static [&'static str],
+    pub types: &'static [&'static str],
+    /// Identifiers starting with an uppercase letter are highlighted as types.
+    pub capitalized_types: bool,
+    pub line_comment: Option<&'static str>,
+    pub block_comment: Option<(&'static str, &'static str)>,
+    pub nested_comments: bool,
+    /// Delimiters of strings that end with the line.
+    pub quotes: &'static [&'static str],
+    /// Delimiters of strings that may span several lines.
+    pub multiline_quotes: &'static [&'static str],
+    pub line_start: LineStart,
+    /// A `'` that does not close a character literal starts a lifetime, as in Rust.
+    pub lifetimes: bool,
+    /// Characters that indent the next line when they end a line.
+    pub indent_after: &'static [char],
+}
+
+const RUST: Syntax = Syntax {
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+        "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait",
+        "true", "type", "unsafe", "use", "where", "while",
+    ],
+    types: &[
+        "bool", "char", "str", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32",
+        "i64", "i128", "isize", "f32", "f64",
+    ],
+    capitalized_types: true,
+    line_comment: Some("//"),
+    block_comment: Some(("/*", "*/")),
+    nested_comments: true,
+    quotes: &["'"],
+    multiline_quotes: &["\""],
+    line_start: LineStart::Plain,
+    lifetimes: true,
+    indent_after: &['{', '(', '['],
+};
+
+const C: Syntax = Syntax {
+    keywords: &[
+        "auto", "break", "case", "const", "continue", "default", "do", "else", "enum", "extern",
+        "for", "goto", "if", "inline", "register", "restrict", "return", "sizeof", "static",
+        "struct", "switch", "typedef", "union", "volatile", "while", "NULL", "true", "false",
+    ],
+    types: &[
+        "bool", "char", "double", "float", "int", "long", "short", "signed", "unsigned", "void",
+        "size_t", "int8_t", "int16_t", "int32_t", "int64_t", "uint8_t", "uint16_t", "uint32_t",
+        "uint64_t",
+    ],
+    capitalized_types: false,
+    line_comment: Some("//"),
+    block_comment: Some(("/*", "*/")),
+    nested_comments: false,
+    quotes: &["\"", "'"],
+    multiline_quotes: &[],
+    line_start: LineStart::Directive,
+    lifetimes: false,
+    indent_after: &['{', '(', '['],
+};
+
+const PYTHON: Syntax = Syntax {
+    keywords: &[
+        "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+        "elif", "else", "except", "False", "finally", "for", "from", "global", "if", "import",
+        "in", "is", "lambda", "None", "nonlocal", "not", "or", "pass", "raise", "return", "self",
+        "True", "try", "while", "with", "yield",
+    ],
+    types: &[
+        "bool", "bytes", "dict", "float", "int", "list", "object", "set", "str", "tuple",
+    ],
+    capitalized_types: true,
+    line_comment: Some("#"),
+    block_comment: None,
+    nested_comments: false,
+    quotes: &["\"", "'"],
+    multiline_quotes: &["\"\"\"", "'''"],
+    line_start: LineStart::Plain,
+    lifetimes: false,
+    indent_after: &[':', '{', '(', '['],
+};
+
+const TOML: Syntax = Syntax {
+    keywords: &["true", "false"],
+    types: &[],
+    capitalized_types: false,
+    line_comment: Some("#"),
+    block_comment: None,
+    nested_comments: false,
+    quotes: &["\"", "'"],
+    multiline_quotes: &["\"\"\"", "'''"],
+    line_start: LineStart::SectionHeader,
+    lifetimes: false,
+    indent_after: &['{', '['],
+};
+
+const JSON: Syntax = Syntax {
+    keywords: &["true", "false", "null"],
+    types: &[],
+    capitalized_types: false,
+    line_comment: None,
+    block_comment: None,
+    nested_comments: false,
+    quotes: &["\""],
+    multiline_quotes: &[],
+    line_start: LineStart::Plain,
+    lifetimes: false,
+    indent_after: &['{', '['],
+};
+
+impl Language {
+    pub fn from_path(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default();
+
+        match extension.to_ascii_lowercase().as_str() {
+            "rs" => Self::Rust,
+            "c" | "h" => Self::C,
+            "py" | "pyw" => Self::Python,
+            "toml" => Self::Toml,
+            "md" | "markdown" => Self::Markdown,
+            "json" => Self::Json,
+            _ => Self::PlainText,
+        }
+    }
+
+    /// Token rules for the language, `None` when it is not tokenized like source code.
+    pub const fn syntax(self) -> Option<&'static Syntax> {
+        match self {
+            Self::Rust => Some(&RUST),
+            Self::C => Some(&C),
+            Self::Python => Some(&PYTHON),
+            Self::Toml => Some(&TOML),
+            Self::Json => Some(&JSON),
+            Self::PlainText | Self::Markdown => None,
+        }
+    }
+}
+
+impl Display for Language {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::PlainText => "Plain Text",
+            Self::Rust => "Rust",
+            Self::C => "C",
+            Self::Python => "Python",
+            Self::Toml => "TOML",
+            Self::Markdown => "Markdown",
+            Self::Json => "JSON",
+        };
+        write!(formatter, "{name}")
+    }
+}
@@ -1,3 +1,6 @@
+use crate::annotated_string::AnnotatedString;
+use crate::annotation::Annotation;
+use regex::{Captures, Regex};
 use std::{
     fmt,
     ops::{Deref, Range},
@@ -8,17 +11,26 @@ use unicode_width::UnicodeWidthStr;
 type GraphemeIndex = usize;
 type ByteIndex = usize;
 
+pub const DEFAULT_TAB_WIDTH: usize = 4;
+
 #[derive(Copy, Clone)]
 enum GraphemeWidth {
     Half,
     Full,
+    /// Extends to the next tab stop.
+    Tab,
 }
 
 impl GraphemeWidth {
-    const fn saturating_add(self, other: usize) -> usize {
+    /// Columns taken by a grapheme starting at `column`.
+    const fn columns_at(self, column: usize, tab_width: usize) -> usize {
         match self {
-            Self::Half => other.saturating_add(1),
-            Self::Full => other.saturating_add(2),
+            Self::Half => 1,
+            Self::Full => 2,
+            Self::Tab => {
+                let tab_width = if tab_width == 0 { 1 } else { tab_width };
+                tab_width - column % tab_width
+            }
         }
     }
 }
@@ -50,7 +62,7 @@ impl Line {
         self.fragments
             .iter()
             .position(|fragment| fragment.start_byte_idx >= byte_index)
-            .map_or(0, |grapheme_index| grapheme_index)
+            .unwrap_or(self.fragments.len())
     }
 
     fn grapheme_to_byte_idx(&self, grapheme_index: GraphemeIndex) -> ByteIndex {
@@ -67,8 +79,10 @@ impl Line {
         line_str
             .grapheme_indices(true)
             .map(|(byte_idx, grapheme)| {
-                let (replacement, rendered_width) = Self::get_replacement_character(grapheme)
-                    .map_or_else(
+                let (replacement, rendered_width) = if grapheme == "\t" {
+                    (Some(' '), GraphemeWidth::Tab)
+                } else {
+                    Self::get_replacement_character(grapheme).map_or_else(
                         || {
                             let unicode_width = grapheme.width();
                             let rendered_width = match unicode_width {
@@ -78,7 +92,8 @@ impl Line {
                             (None, rendered_width)
                         },
                         |replacement| (Some(replacement), GraphemeWidth::Half),
-                    );
+                    )
+                };
 
                 TextFragment {
                     grapheme: grapheme.to_string(),
@@ -98,7 +113,6 @@ impl Line {
         let width = for_str.width();
         match for_str {
             " " => None,
-            "\t" => Some(' '),
             _ if width > 0 && for_str.trim().is_empty() => Some('␣'),
             _ if width == 0 => {
                 let mut chars = for_str.chars();
@@ -113,19 +127,22 @@ impl Line {
         }
     }
 
-    pub fn get_visible_graphemes(&self, range: Range<GraphemeIndex>) -> String {
+    pub fn get_visible_graphemes(&self, range: Range<GraphemeIndex>, tab_width: usize) -> String {
         if range.start >= range.end {
             return String::new();
         }
         let mut result = String::new();
-        let mut current_pos = 0;
+        let mut current_pos: usize = 0;
         for fragment in &self.fragments {
-            let fragment_end = fragment.rendered_width.saturating_add(current_pos);
+            let fragment_end = current_pos
+                .saturating_add(fragment.rendered_width.columns_at(current_pos, tab_width));
             if current_pos >= range.end {
                 break;
             }
             if fragment_end > range.start {
-                if fragment_end > range.end || current_pos < range.start {
+                if matches!(fragment.rendered_width, GraphemeWidth::Tab) {
+                    result.push_str(&Self::visible_tab(current_pos..fragment_end, &range));
+                } else if fragment_end > range.end || current_pos < range.start {
                     // Clip on the right or left
                     result.push('⋯');
                 } else if let Some(char) = fragment.replacement {
@@ -139,23 +156,79 @@ impl Line {
         result
     }
 
+    /// Same as `get_visible_graphemes`, but every grapheme keeps the type of the last
+    /// annotation covering it, so later annotations take precedence over earlier ones.
+    pub fn get_annotated_visible_substr(
+        &self,
+        range: Range<GraphemeIndex>,
+        annotations: &[Annotation],
+        tab_width: usize,
+    ) -> AnnotatedString {
+        let mut result = AnnotatedString::default();
+        if range.start >= range.end {
+            return result;
+        }
+        let mut current_pos: usize = 0;
+        for (grapheme_index, fragment) in self.fragments.iter().enumerate() {
+            let fragment_end = current_pos
+                .saturating_add(fragment.rendered_width.columns_at(current_pos, tab_width));
+            if current_pos >= range.end {
+                break;
+            }
+            if fragment_end > range.start {
+                let annotation_type = annotations
+                    .iter()
+                    .rev()
+                    .find(|annotation| annotation.contains(grapheme_index))
+                    .map(|annotation| annotation.kind);
+
+                if matches!(fragment.rendered_width, GraphemeWidth::Tab) {
+                    let spaces = Self::visible_tab(current_pos..fragment_end, &range);
+                    result.push_str(&spaces, annotation_type);
+                } else if fragment_end > range.end || current_pos < range.start {
+                    result.push_str("⋯", annotation_type);
+                } else if let Some(char) = fragment.replacement {
+                    result.push_str(&char.to_string(), annotation_type);
+                } else {
+                    result.push_str(&fragment.grapheme, annotation_type);
+                }
+            }
+            current_pos = fragment_end;
+        }
+        result
+    }
+
+    /// A tab is drawn as blanks, so it can be clipped to the columns that are visible.
+    fn visible_tab(columns: Range<usize>, visible: &Range<usize>) -> String {
+        let start = columns.start.max(visible.start);
+        let end = columns.end.min(visible.end);
+        " ".repeat(end.saturating_sub(start))
+    }
+
+    pub fn get_substring(&self, range: Range<GraphemeIndex>) -> String {
+        self.fragments
+            .iter()
+            .skip(range.start)
+            .take(range.end.saturating_sub(range.start))
+            .map(|fragment| fragment.grapheme.as_str())
+            .collect()
+    }
+
     pub fn grapheme_count(&self) -> GraphemeIndex {
         self.fragments.len()
     }
 
-    pub fn width_until(&self, grapheme_index: GraphemeIndex) -> GraphemeIndex {
+    pub fn width_until(&self, grapheme_index: GraphemeIndex, tab_width: usize) -> GraphemeIndex {
         self.fragments
             .iter()
             .take(grapheme_index)
-            .map(|fragment| match fragment.rendered_width {
-                GraphemeWidth::Half => 1,
-                GraphemeWidth::Full => 2,
+            .fold(0, |column, fragment| {
+                column.saturating_add(fragment.rendered_width.columns_at(column, tab_width))
             })
-            .sum()
     }
 
-    pub fn width(&self) -> GraphemeIndex {
-        self.width_until(self.grapheme_count())
+    pub fn width(&self, tab_width: usize) -> GraphemeIndex {
+        self.width_until(self.grapheme_count(), tab_width)
     }
 
     pub fn insert_char(&mut self, character: char, at: usize) {
@@ -201,25 +274,111 @@ impl Line {
         }
     }
 
+    /// Byte ranges of the non-overlapping, non-empty matches of `pattern`.
+    fn match_ranges<'a>(
+        &'a self,
+        pattern: &'a Regex,
+    ) -> impl Iterator<Item = Range<ByteIndex>> + 'a {
+        pattern
+            .find_iter(&self.string)
+            .filter(|found| !found.is_empty())
+            .map(|found| found.range())
+    }
+
+    pub fn find_all(&self, pattern: &Regex) -> Vec<Range<GraphemeIndex>> {
+        self.match_ranges(pattern)
+            .map(|range| {
+                self.byte_to_grapheme_idx(range.start)..self.byte_to_grapheme_idx(range.end)
+            })
+            .collect()
+    }
+
+    /// Returns where the match of `pattern` starting at `grapheme_index` ends, and the text
+    /// replacing it. When `expand` is set, `$1` and `${name}` refer to capture groups.
+    pub fn replacement_at(
+        &self,
+        grapheme_index: GraphemeIndex,
+        pattern: &Regex,
+        replacement: &str,
+        expand: bool,
+    ) -> Option<(GraphemeIndex, String)> {
+        if grapheme_index >= self.grapheme_count() {
+            return None;
+        }
+        let byte_index = self.grapheme_to_byte_idx(grapheme_index);
+        pattern.captures_iter(&self.string).find_map(|captures| {
+            let found = captures.get(0)?;
+            (found.start() == byte_index && !found.is_empty()).then(|| {
+                (
+                    self.byte_to_grapheme_idx(found.end()),
+                    Self::expand_replacement(&captures, replacement, expand),
+                )
+            })
+        })
+    }
+
+    /// Returns the text of the line with every match starting at or after `from` replaced,
+    /// along with the number of replaced matches.
+    pub fn replace_matches_from(
+        &self,
+        from: GraphemeIndex,
+        pattern: &Regex,
+        replacement: &str,
+        expand: bool,
+    ) -> (String, usize) {
+        if from >= self.grapheme_count() {
+            return (self.string.clone(), 0);
+        }
+        let from_byte = self.grapheme_to_byte_idx(from);
+
+        let mut result = String::new();
+        let mut last_end = 0;
+        let mut replaced = 0;
+        for captures in pattern.captures_iter(&self.string) {
+            let Some(found) = captures.get(0) else {
+                continue;
+            };
+            if found.is_empty() || found.start() < from_byte {
+                continue;
+            }
+            result.push_str(&self.string[last_end..found.start()]);
+            result.push_str(&Self::expand_replacement(&captures, replacement, expand));
+            last_end = found.end();
+            replaced += 1;
+        }
+        result.push_str(&self.string[last_end..]);
+        (result, replaced)
+    }
+
+    fn expand_replacement(captures: &Captures, replacement: &str, expand: bool) -> String {
+        if expand {
+            let mut expanded = String::new();
+            captures.expand(replacement, &mut expanded);
+            expanded
+        } else {
+            replacement.to_string()
+        }
+    }
+
     pub fn search_forward(
         &self,
         from_grapheme_idx: GraphemeIndex,
-        query: &str,
+        pattern: &Regex,
     ) -> Option<GraphemeIndex> {
         debug_assert!(from_grapheme_idx <= self.grapheme_count());
         if from_grapheme_idx == self.grapheme_count() {
             return None;
         }
         let start_byte_idx = self.grapheme_to_byte_idx(from_grapheme_idx);
-        self.string
-            .get(start_byte_idx..)
-            .and_then(|substr| substr.find(query))
-            .map(|byte_idx| self.byte_to_grapheme_idx(byte_idx.saturating_add(start_byte_idx)))
+        self.match_ranges(pattern)
+            .find(|range| range.start >= start_byte_idx)
+            .map(|range| self.byte_to_grapheme_idx(range.start))
     }
+
     pub fn search_backward(
         &self,
         from_grapheme_idx: GraphemeIndex,
-        query: &str,
+        pattern: &Regex,
     ) -> Option<GraphemeIndex> {
         debug_assert!(from_grapheme_idx <= self.grapheme_count());
 
@@ -231,10 +390,10 @@ impl Line {
         } else {
             self.grapheme_to_byte_idx(from_grapheme_idx)
         };
-        self.string
-            .get(..end_byte_index)
-            .and_then(|substr| substr.match_indices(query).last())
-            .map(|(index, _)| self.byte_to_grapheme_idx(index))
+        self.match_ranges(pattern)
+            .filter(|range| range.start < end_byte_index)
+            .last()
+            .map(|range| self.byte_to_grapheme_idx(range.start))
     }
 }
 
@@ -0,0 +1,87 @@
+use std::fmt::{self, Display};
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+    /// Both styles appear in the file. The `'\r'` of the CRLF lines is kept in the
+    /// text, so the file is written back exactly as it was read.
+    Mixed,
+}
+
+impl LineEnding {
+    /// Detects the style of `lines`, the file content split at every `'\n'`.
+    /// The last element is not terminated and is ignored.
+    pub fn detect(lines: &[&str]) -> Self {
+        let terminated = &lines[..lines.len().saturating_sub(1)];
+        let crlf = terminated
+            .iter()
+            .filter(|line| line.ends_with('\r'))
+            .count();
+        match crlf {
+            0 => Self::Lf,
+            _ if crlf == terminated.len() => Self::Crlf,
+            _ => Self::Mixed,
+        }
+    }
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Lf | Self::Mixed => "\n",
+            Self::Crlf => "\r\n",
+        }
+    }
+
+    /// The style the convert command switches to.
+    pub const fn toggled(self) -> Self {
+        match self {
+            Self::Lf => Self::Crlf,
+            Self::Crlf | Self::Mixed => Self::Lf,
+        }
+    }
+}
+
+impl Display for LineEnding {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Lf => "LF",
+            Self::Crlf => "CRLF",
+            Self::Mixed => "Mixed",
+        };
+        write!(formatter, "{name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(content: &str) -> LineEnding {
+        LineEnding::detect(&content.split('\n').collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn detects_the_style_of_terminated_lines() {
+        assert_eq!(detect("one\ntwo\n"), LineEnding::Lf);
+        assert_eq!(detect("one\r\ntwo\r\n"), LineEnding::Crlf);
+        assert_eq!(detect("one\r\ntwo\n"), LineEnding::Mixed);
+    }
+
+    #[test]
+    fn ignores_the_unterminated_last_line() {
+        assert_eq!(detect("one\r\ntwo\r"), LineEnding::Crlf);
+        assert_eq!(detect("one\ntwo\r"), LineEnding::Lf);
+        assert_eq!(detect("single line\r"), LineEnding::Lf);
+        assert_eq!(detect(""), LineEnding::Lf);
+    }
+
+    #[test]
+    fn toggles_between_lf_and_crlf() {
+        assert_eq!(LineEnding::Lf.toggled(), LineEnding::Crlf);
+        assert_eq!(LineEnding::Crlf.toggled(), LineEnding::Lf);
+        assert_eq!(LineEnding::Mixed.toggled(), LineEnding::Lf);
+        assert_eq!(LineEnding::Crlf.as_str(), "\r\n");
+        assert_eq!(LineEnding::Mixed.as_str(), "\n");
+    }
+}
@@ -1,34 +1,58 @@
 #![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
 
 mod buffer;
+mod clipboard;
 mod editor;
 mod editor_commands;
 mod line;
 mod terminal;
+mod theme;
 mod view;
+use config::Config;
 use multi_editor::MultiEditor;
+mod annotated_string;
+mod annotation;
+mod annotation_type;
 mod args;
+mod atomic_file;
+mod backup;
 mod command_bar;
+mod config;
+mod diff;
+mod disk_state;
 mod document_status;
 mod file_info;
+mod goto_target;
+mod highlighter;
+mod history;
+mod key_chord;
+mod keymap;
+mod language;
+mod line_ending;
 mod location;
 mod message_bar;
 mod multi_editor;
+mod path_completion;
 mod position;
+mod register;
 mod serach_info;
 mod size;
 mod status_bar;
+mod swap_file;
+mod text_encoding;
 mod ui_component;
 
-/* TODO! :
- * add option to open a file from the command bar
- * some visual feedback for find
-*/
-
 fn main() {
-    let mut ed = MultiEditor::new();
-    let files = args::parse_args();
-    ed.load(&files);
+    let options = args::parse_args();
+    let mut warnings = Vec::new();
+    let mut config = Config::load(options.config.as_deref()).unwrap_or_else(|error| {
+        warnings.push(error);
+        Config::default()
+    });
+    options.override_config(&mut config);
+
+    let mut ed = MultiEditor::new(config, warnings);
+    ed.load(&options.files);
     MultiEditor::init().unwrap();
     ed.run();
 }
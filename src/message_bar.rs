@@ -1,5 +1,7 @@
 use crate::size::Size;
-use crate::{terminal::Terminal, ui_component::UiComponent};
+use crate::terminal::Terminal;
+use crate::theme::{Scope, Theme};
+use crate::ui_component::UiComponent;
 use std::time::{Duration, Instant};
 
 const DEFAULT_DURATION: Duration = Duration::new(3, 0);
@@ -19,20 +21,36 @@ impl Default for Message {
 }
 
 impl Message {
-    fn is_expired(&self) -> bool {
-        Instant::now().duration_since(self.time) > DEFAULT_DURATION
+    fn is_expired(&self, timeout: Duration) -> bool {
+        Instant::now().duration_since(self.time) > timeout
     }
 }
 
-#[derive(Default)]
 pub struct MessageBar {
     message: Message,
     needs_redraw: bool,
     size: Size,
     message_cleared: bool,
+    timeout: Duration,
+}
+
+impl Default for MessageBar {
+    fn default() -> Self {
+        Self {
+            message: Message::default(),
+            needs_redraw: false,
+            size: Size::default(),
+            message_cleared: false,
+            timeout: DEFAULT_DURATION,
+        }
+    }
 }
 
 impl MessageBar {
+    pub const fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
     pub fn update_message(&mut self, new_message: &str) {
         self.message = Message {
             text: new_message.to_string(),
@@ -53,7 +71,7 @@ impl UiComponent for MessageBar {
     }
 
     fn needs_redraw(&self) -> bool {
-        (!self.message_cleared && self.message.is_expired()) || self.needs_redraw
+        (!self.message_cleared && self.message.is_expired(self.timeout)) || self.needs_redraw
     }
 
     fn set_size(&mut self, size: Size) {
@@ -61,16 +79,16 @@ impl UiComponent for MessageBar {
         self.mark_redraw(true);
     }
 
-    fn draw(&mut self, position_y: usize) -> Result<(), std::io::Error> {
-        if self.message.is_expired() {
+    fn draw(&mut self, position_y: usize, theme: &Theme) -> Result<(), std::io::Error> {
+        if self.message.is_expired(self.timeout) {
             self.message_cleared = true;
         }
 
-        let message = if self.message.is_expired() {
+        let message = if self.message.is_expired(self.timeout) {
             String::new()
         } else {
             self.message.text.to_string()
         };
-        Terminal::print_row(position_y, &message)
+        Terminal::print_styled_row(position_y, &message, theme.style(Scope::MessageBar))
     }
 }
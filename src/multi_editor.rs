@@ -1,39 +1,86 @@
+use crate::args::FileArgument;
+use crate::clipboard::Clipboard;
+use crate::config::Config;
 use crate::editor_commands::{
-    Command::System,
-    System::{Quit, Resize},
+    Command::{Edit, System},
+    Edit::{Copy, Cut, Paste},
+    System::{Dismiss, NewEditor, NextEditor, PreviousEditor, Quit, QuitAll, Resize, SaveAll},
 };
-use crate::{editor::Editor, editor_commands::Command, size::Size, terminal::Terminal};
-use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers, read};
+use crate::goto_target::GotoTarget;
+use crate::keymap::{KeyResolution, Keymap};
+use crate::register::Register;
+use crate::text_encoding::TextEncoding;
+use crate::theme::Theme;
+use crate::{
+    editor::{Editor, QuitReview},
+    editor_commands::Command,
+    size::Size,
+    terminal::Terminal,
+};
+use crossterm::event::{Event, KeyEventKind, poll, read};
 use std::io::Error;
+use std::path::Path;
+use std::time::{Duration, Instant};
 use std::vec;
 
+/// How long to wait for an event before the periodic work, such as writing swap files, is done.
+const IDLE_TICK: Duration = Duration::from_millis(500);
+
+/// How often the open files are checked for changes made by other programs.
+const DISK_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
 pub struct MultiEditor {
     editors: Vec<Editor>,
     active_editor: usize,
     terminal_size: Size,
     should_quit: bool,
-}
-
-impl Default for MultiEditor {
-    fn default() -> Self {
-        Self {
-            editors: vec![Editor::new()],
-            active_editor: 0,
-            terminal_size: Size::default(),
-            should_quit: false,
-        }
-    }
+    clipboard: Clipboard,
+    theme: Theme,
+    keymap: Keymap,
+    config: Config,
+    last_swap: Instant,
+    last_disk_check: Instant,
+    /// Quit all is waiting for the unsaved changes of every editor to be saved or discarded.
+    reviewing_quit: bool,
 }
 
 impl MultiEditor {
-    pub fn new() -> Self {
+    /// `warnings` are shown once the editor starts, together with the ones found here.
+    pub fn new(config: Config, mut warnings: Vec<String>) -> Self {
         let current_hook = std::panic::take_hook();
         std::panic::set_hook(Box::new(move |panic_info| {
             let _ = Terminal::terminate();
             current_hook(panic_info);
         }));
 
-        let mut multi_editor = Self::default();
+        let (keymap, keymap_warnings) = Keymap::new(&config.keys);
+        let mut multi_editor = Self {
+            editors: vec![Editor::new(&config)],
+            active_editor: 0,
+            terminal_size: Size::default(),
+            should_quit: false,
+            clipboard: Clipboard::new(config.clipboard),
+            theme: Theme::default(),
+            keymap,
+            config,
+            last_swap: Instant::now(),
+            last_disk_check: Instant::now(),
+            reviewing_quit: false,
+        };
+        if let Err(error) = multi_editor.apply_theme() {
+            warnings.push(error);
+        }
+        warnings.extend(keymap_warnings);
+        if TextEncoding::from_label(&multi_editor.config.fallback_encoding).is_none() {
+            warnings.push(format!(
+                "Unknown fallback encoding `{}`, using {}",
+                multi_editor.config.fallback_encoding,
+                TextEncoding::DEFAULT_FALLBACK
+            ));
+        }
+        if !warnings.is_empty() {
+            multi_editor.warn(&warnings.join("; "));
+        }
         let size = Terminal::size().unwrap_or_default();
         multi_editor.resize(size);
         multi_editor
@@ -46,6 +93,18 @@ impl MultiEditor {
         }
     }
 
+    fn apply_theme(&mut self) -> Result<(), String> {
+        self.theme = Theme::load(&self.config.theme)?;
+        for editor in &mut self.editors {
+            editor.set_needs_redraw(true);
+        }
+        Ok(())
+    }
+
+    fn warn(&mut self, message: &str) {
+        self.change_editor_message(&format!("WARNING: {message}"));
+    }
+
     pub fn init() -> Result<(), Error> {
         Terminal::init()
     }
@@ -54,22 +113,14 @@ impl MultiEditor {
         &mut self.editors[self.active_editor]
     }
 
-    pub fn load(&mut self, file_names: &[String]) {
-        if file_names.is_empty() {
-            return;
-        }
-
-        self.active_editor().load(&file_names[0]);
-
-        for file_name in file_names.iter().skip(1) {
-            self.create_new_editor();
-            self.active_editor += 1;
-            self.active_editor().load(file_name);
+    pub fn load(&mut self, files: &[FileArgument]) {
+        for file in files {
+            self.open(&file.file_name, file.target);
         }
     }
 
     fn refresh_screen(&mut self) {
-        self.active_editor().refresh_screen();
+        self.editors[self.active_editor].refresh_screen(&self.theme);
     }
 
     fn change_editor_message(&mut self, message: &str) {
@@ -86,8 +137,10 @@ impl MultiEditor {
                 break;
             }
 
-            match read() {
-                Ok(event) => self.evaluate_event(event),
+            let event = poll(IDLE_TICK).and_then(|ready| ready.then(read).transpose());
+            match event {
+                Ok(Some(event)) => self.evaluate_event(&event),
+                Ok(None) => {}
                 Err(e) => {
                     #[cfg(debug_assertions)]
                     {
@@ -95,55 +148,51 @@ impl MultiEditor {
                     }
                 }
             }
+            self.write_swap_files();
+            self.check_files_on_disk();
         }
     }
 
-    fn evaluate_event(&mut self, event: Event) {
-        if let Event::Key(KeyEvent {
-            code: KeyCode::Char('p'),
-            modifiers: KeyModifiers::CONTROL,
-            kind: crossterm::event::KeyEventKind::Press,
-            ..
-        }) = event
-        {
-            let editor_index = self.active_editor.saturating_add(1);
-            self.switch_editor(editor_index);
+    fn check_files_on_disk(&mut self) {
+        if self.last_disk_check.elapsed() < DISK_CHECK_INTERVAL {
             return;
         }
-
-        if let Event::Key(KeyEvent {
-            code: KeyCode::Char('o'),
-            modifiers: KeyModifiers::CONTROL,
-            kind: crossterm::event::KeyEventKind::Press,
-            ..
-        }) = event
-        {
-            let editor_index = self.active_editor.saturating_sub(1);
-            self.switch_editor(editor_index);
-            return;
+        for editor in &mut self.editors {
+            editor.check_disk();
         }
+        self.last_disk_check = Instant::now();
+    }
 
-        if let Event::Key(KeyEvent {
-            code: KeyCode::Char('n'),
-            modifiers: KeyModifiers::CONTROL,
-            kind: crossterm::event::KeyEventKind::Press,
-            ..
-        }) = event
-        {
-            self.create_new_editor();
+    /// Writes the unsaved changes of every editor to its swap file once the interval passed.
+    fn write_swap_files(&mut self) {
+        let interval = Duration::from_secs(self.config.swap_interval);
+        if interval.is_zero() || self.last_swap.elapsed() < interval {
             return;
         }
+        for editor in &mut self.editors {
+            editor.write_swap();
+        }
+        self.last_swap = Instant::now();
+    }
 
-        let should_process = match &event {
-            Event::Key(KeyEvent { kind, .. }) => kind == &crossterm::event::KeyEventKind::Press,
-            Event::Resize(_, _) => true,
-            _ => false,
-        };
-
-        if should_process {
-            if let Ok(command) = Command::try_from(event) {
-                self.process_command(command);
+    fn evaluate_event(&mut self, event: &Event) {
+        match *event {
+            Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
+                match self.keymap.resolve(key_event) {
+                    KeyResolution::Command(command) => self.process_command(command),
+                    KeyResolution::Pending(keys) => {
+                        self.change_editor_message(&format!("{keys} ..."));
+                    }
+                    KeyResolution::Unbound => {}
+                }
             }
+            Event::Resize(width_u16, height_u16) => {
+                self.process_command(System(Resize(Size {
+                    width: width_u16 as usize,
+                    height: height_u16 as usize,
+                })));
+            }
+            _ => {}
         }
     }
 
@@ -158,19 +207,28 @@ impl MultiEditor {
     }
 
     fn create_new_editor(&mut self) {
-        self.editors.push(Editor::new());
+        self.editors.push(Editor::new(&self.config));
         self.change_editor_message(&format!(
             "New editor created, {} editors are open",
             self.editors.iter().len()
         ));
     }
 
+    fn store_in_clipboard(&mut self, register: Register) {
+        if let Err(error) = self.clipboard.store(register) {
+            self.warn(&format!(
+                "Could not reach the system clipboard ({error}), copied internally"
+            ));
+        }
+    }
+
     fn process_command(&mut self, command: Command) {
         match command {
             System(Quit) => {
                 self.active_editor().process_command(command);
 
                 if self.active_editor().should_quit {
+                    self.active_editor().close();
                     if self.editors.len() <= 1 {
                         self.should_quit = true;
                     } else {
@@ -186,8 +244,137 @@ impl MultiEditor {
             System(Resize(size)) => {
                 self.resize(size);
             }
+            System(NextEditor) => {
+                let editor_index = self.active_editor.saturating_add(1);
+                self.switch_editor(editor_index);
+            }
+            System(PreviousEditor) => {
+                let editor_index = self.active_editor.saturating_sub(1);
+                self.switch_editor(editor_index);
+            }
+            System(NewEditor) => self.create_new_editor(),
+            System(SaveAll) => self.save_all(),
+            System(QuitAll) => self.quit_all(),
+            Edit(Copy) => {
+                if let Some(register) = self.active_editor().copy() {
+                    self.store_in_clipboard(register);
+                }
+            }
+            Edit(Cut) => {
+                if let Some(register) = self.active_editor().cut() {
+                    self.store_in_clipboard(register);
+                }
+            }
+            Edit(Paste) => {
+                let register = self.clipboard.load();
+                self.active_editor().paste(&register);
+            }
             _ => self.active_editor().process_command(command),
         }
+
+        if let Some(file_name) = self.active_editor().file_to_open.take() {
+            self.open(&file_name, None);
+        }
+        if let Some(text) = self.active_editor().text_to_show.take() {
+            self.show_text(&text);
+        }
+        if self.reviewing_quit {
+            self.continue_quit_review();
+        }
+    }
+
+    fn save_all(&mut self) {
+        let mut saved = 0;
+        let mut failed = Vec::new();
+        for editor in &mut self.editors {
+            match editor.save_modified() {
+                Ok(true) => saved += 1,
+                Ok(false) => {}
+                Err(reason) => failed.push(format!("{} ({reason})", editor.file_name())),
+            }
+        }
+        let message = if failed.is_empty() {
+            format!("Saved {saved} file(s)")
+        } else {
+            format!("Saved {saved} file(s), not saved: {}", failed.join(", "))
+        };
+        self.change_editor_message(&message);
+    }
+
+    fn quit_all(&mut self) {
+        for editor in &mut self.editors {
+            editor.quit_review = QuitReview::Pending;
+        }
+        if self.active_editor().in_prompt() {
+            self.active_editor().process_command(System(Dismiss));
+        }
+        self.reviewing_quit = true;
+        self.continue_quit_review();
+    }
+
+    /// Asks about one editor with unsaved changes after the other, and quits once
+    /// every change was saved or discarded.
+    fn continue_quit_review(&mut self) {
+        if self.active_editor().in_prompt() {
+            return;
+        }
+        if self.active_editor().quit_review == QuitReview::Cancelled {
+            self.reviewing_quit = false;
+            self.change_editor_message("Quit cancelled");
+            return;
+        }
+
+        let unsaved: Vec<usize> = (0..self.editors.len())
+            .filter(|&index| self.editors[index].has_unsaved_changes())
+            .collect();
+        let Some(&first) = unsaved.first() else {
+            for editor in &mut self.editors {
+                editor.close();
+            }
+            self.reviewing_quit = false;
+            self.should_quit = true;
+            return;
+        };
+        let names: Vec<String> = unsaved
+            .iter()
+            .map(|&index| self.editors[index].file_name())
+            .collect();
+        if first != self.active_editor {
+            self.switch_editor(first);
+        }
+        self.active_editor().review_for_quit(&names);
+    }
+
+    fn show_text(&mut self, text: &str) {
+        self.create_new_editor();
+        self.active_editor = self.editors.len().saturating_sub(1);
+        let _ = Terminal::clear();
+        self.active_editor().show_text(text);
+        self.active_editor().set_needs_redraw(true);
+        self.change_editor_message(&format!("Opened in editor window {}", self.active_editor));
+    }
+
+    /// Loads `file_name` into a new editor, or switches to the one that has it open
+    /// already: two editors on one file would overwrite each other's changes.
+    fn open(&mut self, file_name: &str, target: Option<GotoTarget>) {
+        if let Some(index) = self
+            .editors
+            .iter()
+            .position(|editor| editor.is_editing(Path::new(file_name)))
+        {
+            self.switch_editor(index);
+            self.change_editor_message(&format!(
+                "{file_name} is already open in editor window {index}"
+            ));
+            return;
+        }
+        if !self.active_editor().is_untouched() {
+            self.create_new_editor();
+            self.active_editor = self.editors.len().saturating_sub(1);
+            let _ = Terminal::clear();
+        }
+        self.active_editor().load(file_name, target);
+        self.active_editor().set_needs_redraw(true);
     }
 }
 
@@ -0,0 +1,60 @@
+use std::fs::read_dir;
+
+const SEPARATOR: char = '/';
+
+/// Result of completing a partially typed path against the filesystem.
+pub struct PathCompletion {
+    /// The input extended by the longest prefix shared by every candidate.
+    pub completed: String,
+    /// File names matching the typed prefix, directories end with `/`.
+    pub candidates: Vec<String>,
+}
+
+pub fn complete_path(input: &str) -> PathCompletion {
+    let (directory, prefix) = input
+        .rfind(SEPARATOR)
+        .map_or(("", input), |index| input.split_at(index.saturating_add(1)));
+    let search_directory = if directory.is_empty() { "." } else { directory };
+
+    let mut candidates: Vec<String> = read_dir(search_directory)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let mut name = entry.file_name().into_string().ok()?;
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+            if entry.path().is_dir() {
+                name.push(SEPARATOR);
+            }
+            Some(name)
+        })
+        .collect();
+    candidates.sort();
+
+    let completed = candidates.first().map_or_else(
+        || input.to_string(),
+        |first| {
+            let common = candidates
+                .iter()
+                .skip(1)
+                .fold(first.as_str(), |common, name| {
+                    &common[..common_prefix_len(common, name)]
+                });
+            format!("{directory}{common}")
+        },
+    );
+
+    PathCompletion {
+        completed,
+        candidates,
+    }
+}
+
+fn common_prefix_len(a: &str, b: &str) -> usize {
+    a.char_indices()
+        .zip(b.chars())
+        .find(|((_, a), b)| a != b)
+        .map_or_else(|| a.len().min(b.len()), |((index, _), _)| index)
+}
@@ -0,0 +1,7 @@
+/// Text stored by copy and cut, shared by every editor window.
+#[derive(Default, Clone)]
+pub struct Register {
+    pub text: String,
+    /// Whole lines were copied, so pasting inserts them above the caret line.
+    pub linewise: bool,
+}
@@ -1,8 +1,103 @@
 use crate::line::Line;
 use crate::{location::Location, position::Position};
+use regex::{Regex, RegexBuilder};
+
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+pub enum CaseMode {
+    #[default]
+    Sensitive,
+    Insensitive,
+    /// Case sensitive only when the query contains an uppercase letter.
+    Smart,
+}
+
+impl CaseMode {
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Sensitive => Self::Insensitive,
+            Self::Insensitive => Self::Smart,
+            Self::Smart => Self::Sensitive,
+        }
+    }
+
+    fn ignores_case(self, query: &str) -> bool {
+        match self {
+            Self::Sensitive => false,
+            Self::Insensitive => true,
+            Self::Smart => !query.chars().any(char::is_uppercase),
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+pub struct SearchOptions {
+    pub regex: bool,
+    pub case_mode: CaseMode,
+    pub whole_word: bool,
+}
+
+impl SearchOptions {
+    /// Compiles the query typed in the Find prompt. Wrapping the query in slashes,
+    /// as in `/pattern/`, turns on regex mode for that query only.
+    pub fn build_pattern(self, query: &str) -> Result<Regex, String> {
+        let (query, regex) = self.parse_query(query);
+
+        let mut pattern = if regex {
+            query.to_string()
+        } else {
+            regex::escape(query)
+        };
+
+        if self.whole_word {
+            pattern = format!(r"\b(?:{pattern})\b");
+        }
+
+        RegexBuilder::new(&pattern)
+            .case_insensitive(self.case_mode.ignores_case(query))
+            .build()
+            .map_err(|error| {
+                // Syntax errors span several lines, with the last one describing the problem
+                let message = error.to_string();
+                message.lines().last().unwrap_or_default().to_string()
+            })
+    }
+
+    /// Splits the query into the actual pattern and whether it should be treated as a regex.
+    pub fn parse_query(self, query: &str) -> (&str, bool) {
+        query
+            .strip_prefix('/')
+            .and_then(|q| q.strip_suffix('/'))
+            .map_or((query, self.regex), |inner| (inner, true))
+    }
+
+    pub fn label(self) -> String {
+        let mut active = Vec::new();
+        if self.regex {
+            active.push("regex");
+        }
+        match self.case_mode {
+            CaseMode::Sensitive => {}
+            CaseMode::Insensitive => active.push("ignore case"),
+            CaseMode::Smart => active.push("smart case"),
+        }
+        if self.whole_word {
+            active.push("whole word");
+        }
+
+        if active.is_empty() {
+            String::new()
+        } else {
+            format!(" [{}]", active.join(", "))
+        }
+    }
+}
 
 pub struct SearchInfo {
     pub prev_location: Location,
     pub prev_scroll_offset: Position,
     pub query: Option<Line>,
+    pub pattern: Option<Regex>,
+    pub options: SearchOptions,
+    /// Where every match of `pattern` starts, and the buffer revision they were found in.
+    pub match_starts: Option<(u64, Vec<Location>)>,
 }
@@ -1,6 +1,7 @@
 use crate::terminal::Terminal;
 use crate::size::Size;
 use crate::document_status::DocumentStatus;
+use crate::theme::{Scope, Theme};
 use crate::ui_component::UiComponent;
 
 #[derive(Default)]
@@ -34,7 +35,7 @@ impl UiComponent for StatusBar {
         self.size = size;
     }
 
-    fn draw(&mut self, position_y: usize) -> Result<(), std::io::Error> {
+    fn draw(&mut self, position_y: usize, theme: &Theme) -> Result<(), std::io::Error> {
         if let Ok(size) = Terminal::size() {
             let line_count = self.status.line_count_to_string();
             let modified_indicator = self.status.modified_indicator_to_string();
@@ -44,7 +45,12 @@ impl UiComponent for StatusBar {
                 self.status.file_name
             );
 
-            let position_indicator = self.status.position_indicator_to_string();
+            let position_indicator = format!(
+                "{} | {} | {}",
+                self.status.file_type,
+                self.status.file_format,
+                self.status.position_indicator_to_string()
+            );
             let remainder_len = size.width.saturating_sub(beginning.len());
             let status = format!("{beginning}{position_indicator:>remainder_len$}");
 
@@ -54,7 +60,7 @@ impl UiComponent for StatusBar {
                 String::new()
             };
 
-            let result = Terminal::print_inverted_row(position_y, &to_print);
+            let result = Terminal::print_status_row(position_y, &to_print, theme.style(Scope::StatusBar));
             debug_assert!(result.is_ok(), "Failed to render status bar");
             self.mark_redraw(false);
         }
@@ -0,0 +1,343 @@
+use crate::atomic_file::write_private;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+use std::sync::OnceLock;
+
+/// First line of every swap file, so a stray file is never taken for one. The second
+/// line names the process writing it.
+const HEADER: &str = "ed swap file";
+
+/// How many alternate names `.name.ed-swp1`, `.name.ed-swp2`, … are tried while the
+/// plain one is taken by another session.
+const ALTERNATE_NAMES: usize = 9;
+
+/// The process that writes a swap file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Owner {
+    pub pid: u32,
+    pub host: String,
+}
+
+impl Owner {
+    fn current() -> Self {
+        Self {
+            pid: process::id(),
+            host: host_name().to_string(),
+        }
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let (pid, host) = line.split_once(' ')?;
+        Some(Self {
+            pid: pid.parse().ok()?,
+            host: host.to_string(),
+        })
+    }
+
+    /// Processes on other hosts can not be checked and are taken to be alive.
+    fn is_alive(&self) -> bool {
+        if self.host != host_name() {
+            return true;
+        }
+        self.pid != process::id() && process_exists(self.pid)
+    }
+}
+
+impl Display for Owner {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "process {} on {}", self.pid, self.host)
+    }
+}
+
+/// A swap file of another session.
+#[derive(Debug)]
+pub struct FoundSwap {
+    pub path: PathBuf,
+    /// The session that writes it is still running, its swap file must be left alone.
+    pub in_use_by: Option<Owner>,
+}
+
+/// The unsaved text of a file, kept next to it as `.name.ed-swp` so the changes
+/// survive a crash. Only the owner may read it, whatever the permissions of the file.
+/// A swap file of another session is never overwritten: this session then writes
+/// to an alternate name, and removes the other one only once it was recovered or
+/// discarded.
+#[derive(Debug)]
+pub struct SwapFile {
+    /// Where this session writes, `None` when every name is taken.
+    path: Option<PathBuf>,
+    /// Hash of the text this session wrote, `None` while nothing was written.
+    written: Option<u64>,
+    found: Option<FoundSwap>,
+}
+
+impl SwapFile {
+    pub fn for_file(file: &Path) -> Self {
+        let name = file.file_name().map_or_else(
+            || String::from("file"),
+            |name| name.to_string_lossy().into_owned(),
+        );
+        let mut path = None;
+        let mut found = None;
+        for number in 0..=ALTERNATE_NAMES {
+            let suffix = if number == 0 {
+                String::new()
+            } else {
+                number.to_string()
+            };
+            let candidate = file.with_file_name(format!(".{name}.ed-swp{suffix}"));
+            if !candidate.exists() {
+                path.get_or_insert(candidate);
+            } else if found.is_none() {
+                let in_use_by = read_owner(&candidate).filter(Owner::is_alive);
+                found = Some(FoundSwap {
+                    path: candidate,
+                    in_use_by,
+                });
+            }
+        }
+        Self {
+            path,
+            written: None,
+            found,
+        }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub const fn found(&self) -> Option<&FoundSwap> {
+        self.found.as_ref()
+    }
+
+    /// Writes `text` unless the swap file already holds it.
+    pub fn write(&mut self, text: &str) -> Result<(), Error> {
+        let hash = hash_of(text);
+        if self.written == Some(hash) {
+            return Ok(());
+        }
+        let Some(path) = &self.path else {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                "all swap file names are taken",
+            ));
+        };
+        let owner = Owner::current();
+        let content = format!("{HEADER}\n{} {}\n{text}", owner.pid, owner.host);
+        write_private(path, content.as_bytes())?;
+        self.written = Some(hash);
+        Ok(())
+    }
+
+    /// The text of the found swap file.
+    pub fn read(&self) -> Result<String, Error> {
+        let Some(found) = &self.found else {
+            return Err(Error::new(ErrorKind::NotFound, "no swap file found"));
+        };
+        read_swap(&found.path).map(|(_, text)| text)
+    }
+
+    /// Takes over the found swap file whose `text` was recovered, it is now updated
+    /// and removed like one written by this session.
+    pub fn adopt(&mut self, text: &str) {
+        if let Some(found) = self.found.take() {
+            self.remove();
+            self.path = Some(found.path);
+            self.written = Some(hash_of(text));
+        }
+    }
+
+    /// Deletes the found swap file without recovering it.
+    pub fn discard(&mut self) -> Result<(), Error> {
+        let Some(found) = &self.found else {
+            return Ok(());
+        };
+        match fs::remove_file(&found.path) {
+            Err(error) if error.kind() != ErrorKind::NotFound => Err(error),
+            _ => {
+                self.found = None;
+                Ok(())
+            }
+        }
+    }
+
+    /// Removes the swap file written by this session, the changes were saved or dropped.
+    pub fn remove(&mut self) {
+        if self.written.take().is_some()
+            && let Some(path) = &self.path
+        {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// The owner line and the text of a swap file.
+fn read_swap(path: &Path) -> Result<(String, String), Error> {
+    let content = fs::read_to_string(path)?;
+    content
+        .strip_prefix(HEADER)
+        .and_then(|rest| rest.strip_prefix('\n'))
+        .and_then(|rest| rest.split_once('\n'))
+        .map(|(owner, text)| (owner.to_string(), text.to_string()))
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("{} is not a swap file", path.display()),
+            )
+        })
+}
+
+fn read_owner(path: &Path) -> Option<Owner> {
+    read_swap(path)
+        .ok()
+        .and_then(|(owner, _)| Owner::parse(&owner))
+}
+
+fn host_name() -> &'static str {
+    static HOST_NAME: OnceLock<String> = OnceLock::new();
+    HOST_NAME.get_or_init(|| {
+        fs::read_to_string("/proc/sys/kernel/hostname")
+            .ok()
+            .or_else(|| {
+                Command::new("hostname")
+                    .stderr(Stdio::null())
+                    .output()
+                    .ok()
+                    .filter(|output| output.status.success())
+                    .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+            })
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| String::from("localhost"))
+    })
+}
+
+#[cfg(unix)]
+fn process_exists(pid: u32) -> bool {
+    let proc = Path::new("/proc");
+    if proc.join("self").exists() {
+        return proc.join(pid.to_string()).exists();
+    }
+    Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Without a way to check, the process is taken to be alive.
+#[cfg(not(unix))]
+const fn process_exists(_pid: u32) -> bool {
+    true
+}
+
+fn hash_of(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file_in_fresh_directory(test: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("ed-swap-{test}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        let file = directory.join("notes.txt");
+        fs::write(&file, "saved").unwrap();
+        file
+    }
+
+    fn leave_swap(file: &Path, pid: u32, host: &str) -> PathBuf {
+        let path = file.with_file_name(".notes.txt.ed-swp");
+        fs::write(&path, format!("{HEADER}\n{pid} {host}\nunsaved")).unwrap();
+        path
+    }
+
+    #[test]
+    fn swap_of_ended_session_can_be_recovered() {
+        let file = file_in_fresh_directory("ended");
+        let left = leave_swap(&file, u32::MAX, host_name());
+
+        let mut swap = SwapFile::for_file(&file);
+        let found = swap.found().unwrap();
+        assert_eq!(found.path, left);
+        assert_eq!(found.in_use_by, None);
+        assert_eq!(swap.read().unwrap(), "unsaved");
+
+        swap.adopt("unsaved");
+        assert_eq!(swap.path(), Some(left.as_path()));
+        swap.remove();
+        assert!(!left.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn swap_of_running_session_is_in_use() {
+        let file = file_in_fresh_directory("running");
+        let left = leave_swap(&file, std::os::unix::process::parent_id(), host_name());
+
+        let mut swap = SwapFile::for_file(&file);
+        let owner = swap.found().unwrap().in_use_by.clone().unwrap();
+        assert_eq!(owner.pid, std::os::unix::process::parent_id());
+
+        swap.write("mine").unwrap();
+        let own_path = swap.path().unwrap().to_path_buf();
+        assert_eq!(own_path, file.with_file_name(".notes.txt.ed-swp1"));
+        assert_eq!(read_swap(&left).unwrap().1, "unsaved");
+        swap.remove();
+        assert!(!own_path.exists());
+        assert!(left.exists());
+    }
+
+    #[test]
+    fn swap_of_other_host_is_in_use() {
+        let file = file_in_fresh_directory("host");
+        leave_swap(&file, u32::MAX, "elsewhere");
+
+        let swap = SwapFile::for_file(&file);
+        let owner = swap.found().unwrap().in_use_by.clone().unwrap();
+        assert_eq!(
+            owner.to_string(),
+            format!("process {} on elsewhere", u32::MAX)
+        );
+    }
+
+    #[test]
+    fn swap_names_the_writing_process() {
+        let file = file_in_fresh_directory("owner");
+        let mut swap = SwapFile::for_file(&file);
+        swap.write("text\nmore").unwrap();
+
+        let (owner, text) = read_swap(swap.path().unwrap()).unwrap();
+        assert_eq!(Owner::parse(&owner), Some(Owner::current()));
+        assert_eq!(text, "text\nmore");
+        swap.remove();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn swap_of_private_file_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+        let file = file_in_fresh_directory("private");
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o600)).unwrap();
+
+        let mut swap = SwapFile::for_file(&file);
+        swap.write("unsaved secret").unwrap();
+        let mode = fs::metadata(swap.path().unwrap())
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+        swap.remove();
+    }
+}
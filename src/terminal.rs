@@ -1,10 +1,15 @@
 use crossterm::cursor::{Hide, MoveTo, Show};
-use crossterm::style::{Attribute, Print};
+use crossterm::style::{Attribute, Print, PrintStyledContent, ResetColor, SetAttribute, SetStyle};
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode, size, Clear, ClearType, DisableLineWrap, EnableLineWrap, EnterAlternateScreen, LeaveAlternateScreen, SetTitle};
 use crossterm::{queue, Command};
 use std::io::{stdout, Error, Write};
+use std::sync::OnceLock;
+use crate::annotated_string::AnnotatedString;
 use crate::position::Position;
 use crate::size::Size;
+use crate::theme::{ColorSupport, Style, Theme};
+
+static COLOR_SUPPORT: OnceLock<ColorSupport> = OnceLock::new();
 
 pub struct Terminal;
 
@@ -68,10 +73,31 @@ impl Terminal {
         Ok(())
     }
 
-    pub fn print_row(row: usize, line_text: &str) -> Result<(), Error> {
+    pub fn print_styled(string: &str, style: Style) -> Result<(), Error> {
+        let support = *COLOR_SUPPORT.get_or_init(ColorSupport::detect);
+        Self::queue_command(PrintStyledContent(style.to_content_style(support).apply(string)))?;
+        Ok(())
+    }
+
+    pub fn print_annotated(annotated_string: &AnnotatedString, theme: &Theme) -> Result<(), Error> {
+        for part in annotated_string.parts() {
+            let style = part.annotation_type.map_or(theme.text, |annotation_type| {
+                theme.annotation_style(annotation_type)
+            });
+            Self::print_styled(&part.string, style)?;
+        }
+        Ok(())
+    }
+
+    /// Prints `line_text` on `row`, with the rest of the row cleared in `style`.
+    pub fn print_styled_row(row: usize, line_text: &str, style: Style) -> Result<(), Error> {
+        let support = *COLOR_SUPPORT.get_or_init(ColorSupport::detect);
         Self::move_caret(Position::new(row, 0))?;
+        Self::queue_command(SetStyle(style.to_content_style(support)))?;
         Self::clear_line()?;
         Self::print(line_text)?;
+        Self::queue_command(SetAttribute(Attribute::Reset))?;
+        Self::queue_command(ResetColor)?;
         Ok(())
     }
 
@@ -92,17 +118,15 @@ impl Terminal {
         Ok(())
     }
     
-    pub(crate) fn print_inverted_row(position_y: usize, to_print: &str) -> Result<(), Error> {
+    pub(crate) fn print_status_row(position_y: usize, to_print: &str, style: Style) -> Result<(), Error> {
         let width = Self::size()?.width;
-        Self::print_row(
-            position_y,
-            &format!(
-                "{}{:width$.width$}{}",
-                Attribute::Reverse,
-                to_print,
-                Attribute::Reset
-            ),
-        )
+        Self::print_styled_row(position_y, &format!("{to_print:width$.width$}"), style)
+    }
+
+    /// Sets the system clipboard through the OSC 52 escape sequence.
+    pub fn copy_to_clipboard(text: &str) -> Result<(), Error> {
+        Self::print(&format!("\x1b]52;c;{}\x07", encode_base64(text.as_bytes())))?;
+        Self::execute()
     }
 
     pub fn set_title(title: &str) -> Result<(), Error> {
@@ -119,4 +143,25 @@ impl Terminal {
         Self::queue_command(DisableLineWrap)?;
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b0 = u32::from(chunk[0]);
+        let b1 = chunk.get(1).copied().map_or(0, u32::from);
+        let b2 = chunk.get(2).copied().map_or(0, u32::from);
+        let triple = (b0 << 16) | (b1 << 8) | b2;
+
+        for (i, shift) in [18, 12, 6, 0].into_iter().enumerate() {
+            if i <= chunk.len() {
+                encoded.push(char::from(ALPHABET[((triple >> shift) & 0x3f) as usize]));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
@@ -0,0 +1,220 @@
+use encoding_rs::{Encoding, UTF_8, UTF_16BE, UTF_16LE, WINDOWS_1252};
+use std::fmt::{self, Display};
+use std::io::{Error, ErrorKind};
+
+/// How many bytes are inspected to recognize UTF-16 text without a byte order mark.
+const SNIFF_LEN: usize = 1024;
+
+/// The character encoding a file is read and written in.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TextEncoding(&'static Encoding);
+
+impl Default for TextEncoding {
+    fn default() -> Self {
+        Self(UTF_8)
+    }
+}
+
+impl TextEncoding {
+    /// Used for files that are not valid UTF-8 when the config does not name another encoding.
+    pub const DEFAULT_FALLBACK: Self = Self(WINDOWS_1252);
+
+    /// Looks up an encoding by one of its WHATWG labels, e.g. `utf-16le` or `latin1`.
+    pub fn from_label(label: &str) -> Option<Self> {
+        Encoding::for_label(label.trim().as_bytes()).map(Self)
+    }
+
+    /// Guesses the encoding of `bytes` from its byte order mark, the NUL bytes of
+    /// UTF-16 text and UTF-8 validity, in that order, before settling on `fallback`.
+    /// Returns the encoding and the length of the byte order mark.
+    pub fn detect(bytes: &[u8], fallback: Self) -> (Self, usize) {
+        if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+            return (Self(encoding), bom_len);
+        }
+        if let Some(encoding) = Self::sniff_utf16(bytes) {
+            return (encoding, 0);
+        }
+        if std::str::from_utf8(bytes).is_ok() {
+            return (Self(UTF_8), 0);
+        }
+        (fallback, 0)
+    }
+
+    /// ASCII heavy UTF-16 text has a NUL byte in every other position.
+    fn sniff_utf16(bytes: &[u8]) -> Option<Self> {
+        let sample = &bytes[..bytes.len().min(SNIFF_LEN)];
+        if sample.len() < 2 || !sample.len().is_multiple_of(2) {
+            return None;
+        }
+        let pairs = sample.len() / 2;
+        let zeros_at = |offset: usize| {
+            sample
+                .iter()
+                .skip(offset)
+                .step_by(2)
+                .filter(|byte| **byte == 0)
+                .count()
+        };
+        let (even, odd) = (zeros_at(0), zeros_at(1));
+        if odd * 2 > pairs && even == 0 {
+            Some(Self(UTF_16LE))
+        } else if even * 2 > pairs && odd == 0 {
+            Some(Self(UTF_16BE))
+        } else {
+            None
+        }
+    }
+
+    pub fn is_utf16(self) -> bool {
+        self.0 == UTF_16LE || self.0 == UTF_16BE
+    }
+
+    pub fn bom(self) -> &'static [u8] {
+        if self.0 == UTF_8 {
+            b"\xEF\xBB\xBF"
+        } else if self.0 == UTF_16LE {
+            b"\xFF\xFE"
+        } else if self.0 == UTF_16BE {
+            b"\xFE\xFF"
+        } else {
+            &[]
+        }
+    }
+
+    pub fn decode(self, bytes: &[u8]) -> Result<String, Error> {
+        self.0
+            .decode_without_bom_handling_and_without_replacement(bytes)
+            .map(std::borrow::Cow::into_owned)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("not valid {self}")))
+    }
+
+    pub fn encode(self, text: &str, bom: bool) -> Result<Vec<u8>, Error> {
+        let mut bytes = if bom { self.bom().to_vec() } else { Vec::new() };
+        if self.0 == UTF_16LE {
+            bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+        } else if self.0 == UTF_16BE {
+            bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+        } else {
+            let (encoded, used, had_errors) = self.0.encode(text);
+            if used != self.0 {
+                return Err(Error::new(
+                    ErrorKind::Unsupported,
+                    format!("cannot write {self}"),
+                ));
+            }
+            if had_errors {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("the text has characters that {self} cannot represent"),
+                ));
+            }
+            bytes.extend_from_slice(&encoded);
+        }
+        Ok(bytes)
+    }
+}
+
+impl Display for TextEncoding {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{}", self.0.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoding(label: &str) -> TextEncoding {
+        TextEncoding::from_label(label).unwrap()
+    }
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    #[test]
+    fn detects_byte_order_marks() {
+        let fallback = TextEncoding::DEFAULT_FALLBACK;
+        assert_eq!(
+            TextEncoding::detect(b"\xEF\xBB\xBFtext", fallback),
+            (encoding("utf-8"), 3)
+        );
+        assert_eq!(
+            TextEncoding::detect(b"\xFF\xFEt\0", fallback),
+            (encoding("utf-16le"), 2)
+        );
+        assert_eq!(
+            TextEncoding::detect(b"\xFE\xFF\0t", fallback),
+            (encoding("utf-16be"), 2)
+        );
+    }
+
+    #[test]
+    fn detects_text_without_byte_order_mark() {
+        let fallback = TextEncoding::DEFAULT_FALLBACK;
+        assert_eq!(
+            TextEncoding::detect(&utf16le("plain text"), fallback),
+            (encoding("utf-16le"), 0)
+        );
+        assert_eq!(
+            TextEncoding::detect("naïve".as_bytes(), fallback),
+            (encoding("utf-8"), 0)
+        );
+        assert_eq!(TextEncoding::detect(b"na\xEFve", fallback), (fallback, 0));
+        assert_eq!(
+            TextEncoding::detect(b"na\xEFve", encoding("latin2")),
+            (encoding("latin2"), 0)
+        );
+    }
+
+    #[test]
+    fn sniffs_utf16_from_nul_bytes() {
+        assert_eq!(TextEncoding::sniff_utf16(b"\0a\0b\0"), None, "odd length");
+        assert_eq!(
+            TextEncoding::sniff_utf16(b"\0a\0b\0c\0d"),
+            Some(encoding("utf-16be"))
+        );
+        assert_eq!(
+            TextEncoding::sniff_utf16(b"a\0b\0c\0d\0"),
+            Some(encoding("utf-16le"))
+        );
+        assert_eq!(TextEncoding::sniff_utf16(b"a\0bc\0d\0e"), None, "mixed");
+        assert_eq!(TextEncoding::sniff_utf16(b"abcd"), None);
+        assert_eq!(TextEncoding::sniff_utf16(b""), None);
+    }
+
+    #[test]
+    fn encode_and_decode_round_trip() {
+        for (label, text) in [
+            ("utf-8", "naïve ☃"),
+            ("utf-16le", "naïve ☃ 𝄞"),
+            ("utf-16be", "naïve ☃ 𝄞"),
+            ("windows-1252", "café €"),
+            ("shift_jis", "日本語"),
+        ] {
+            let encoding = encoding(label);
+            for bom in [false, true] {
+                let bytes = encoding.encode(text, bom).unwrap();
+                let (detected, bom_len) = TextEncoding::detect(&bytes, encoding);
+                assert_eq!(bom_len, if bom { encoding.bom().len() } else { 0 });
+                assert_eq!(detected, encoding, "{label}, bom: {bom}");
+                assert_eq!(encoding.decode(&bytes[bom_len..]).unwrap(), text);
+            }
+        }
+    }
+
+    #[test]
+    fn encode_reports_unrepresentable_text() {
+        let error = encoding("windows-1252").encode("日本", false).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+
+        let error = encoding("iso-2022-kr").encode("text", false).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn decode_rejects_invalid_bytes() {
+        let error = encoding("utf-8").decode(b"\xFF").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,372 @@
+use crate::annotation_type::AnnotationType;
+use crossterm::style::{Attribute, Attributes, Color, ContentStyle};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::read_to_string;
+
+/// Parts of the screen that are styled independently of the text annotations.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Scope {
+    Text,
+    StatusBar,
+    MessageBar,
+    CommandBar,
+    Gutter,
+    EmptyLine,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Style {
+    pub foreground: Option<Color>,
+    pub background: Option<Color>,
+    pub attributes: Attributes,
+}
+
+impl Style {
+    const fn new(foreground: Option<Color>, background: Option<Color>) -> Self {
+        Self {
+            foreground,
+            background,
+            attributes: Attributes::none(),
+        }
+    }
+
+    const fn fg(hex: u32) -> Self {
+        Self::new(Some(rgb(hex)), None)
+    }
+
+    const fn bg(hex: u32) -> Self {
+        Self::new(None, Some(rgb(hex)))
+    }
+
+    const fn on(self, hex: u32) -> Self {
+        Self {
+            background: Some(rgb(hex)),
+            ..self
+        }
+    }
+
+    const fn with(self, attribute: Attribute) -> Self {
+        Self {
+            attributes: self.attributes.with(attribute),
+            ..self
+        }
+    }
+
+    /// Fills the colors this style leaves unset with the ones of `base`.
+    pub fn over(self, base: Self) -> Self {
+        Self {
+            foreground: self.foreground.or(base.foreground),
+            background: self.background.or(base.background),
+            attributes: self.attributes,
+        }
+    }
+
+    pub fn to_content_style(self, support: ColorSupport) -> ContentStyle {
+        let mut style = ContentStyle::new();
+        style.foreground_color = self.foreground.map(|color| support.adapt(color));
+        style.background_color = self.background.map(|color| support.adapt(color));
+        style.attributes = self.attributes;
+        style
+    }
+}
+
+/// How many colors the terminal can display.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ColorSupport {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+/// Approximate RGB values of the 16 standard colors, in `Color::AnsiValue` order.
+const ANSI16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::DarkRed, (128, 0, 0)),
+    (Color::DarkGreen, (0, 128, 0)),
+    (Color::DarkYellow, (128, 128, 0)),
+    (Color::DarkBlue, (0, 0, 128)),
+    (Color::DarkMagenta, (128, 0, 128)),
+    (Color::DarkCyan, (0, 128, 128)),
+    (Color::Grey, (192, 192, 192)),
+    (Color::DarkGrey, (128, 128, 128)),
+    (Color::Red, (255, 0, 0)),
+    (Color::Green, (0, 255, 0)),
+    (Color::Yellow, (255, 255, 0)),
+    (Color::Blue, (0, 0, 255)),
+    (Color::Magenta, (255, 0, 255)),
+    (Color::Cyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl ColorSupport {
+    pub fn detect() -> Self {
+        let color_term = env::var("COLORTERM").unwrap_or_default();
+        let term = env::var("TERM").unwrap_or_default();
+
+        if matches!(color_term.as_str(), "truecolor" | "24bit") {
+            Self::TrueColor
+        } else if term.contains("256color") {
+            Self::Ansi256
+        } else {
+            Self::Ansi16
+        }
+    }
+
+    /// Replaces `color` by the closest one the terminal can display.
+    pub fn adapt(self, color: Color) -> Color {
+        match (self, color) {
+            (Self::Ansi256, Color::Rgb { r, g, b }) => Color::AnsiValue(nearest_ansi256((r, g, b))),
+            (Self::Ansi16, Color::Rgb { r, g, b }) => nearest_ansi16((r, g, b)),
+            (Self::Ansi16, Color::AnsiValue(value)) => nearest_ansi16(ansi256_to_rgb(value)),
+            _ => color,
+        }
+    }
+}
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    [(r1, r2), (g1, g2), (b1, b2)]
+        .into_iter()
+        .map(|(a, b)| u32::from(a.abs_diff(b)).pow(2))
+        .sum()
+}
+
+fn nearest_ansi16(rgb: (u8, u8, u8)) -> Color {
+    ANSI16
+        .iter()
+        .min_by_key(|(_, candidate)| distance(rgb, *candidate))
+        .map_or(Color::Reset, |(color, _)| *color)
+}
+
+fn ansi256_to_rgb(value: u8) -> (u8, u8, u8) {
+    match value {
+        0..=15 => ANSI16[usize::from(value)].1,
+        16..=231 => {
+            let index = value - 16;
+            (
+                CUBE_LEVELS[usize::from(index / 36)],
+                CUBE_LEVELS[usize::from(index / 6 % 6)],
+                CUBE_LEVELS[usize::from(index % 6)],
+            )
+        }
+        _ => {
+            let level = 8 + (value - 232) * 10;
+            (level, level, level)
+        }
+    }
+}
+
+fn nearest_ansi256(rgb: (u8, u8, u8)) -> u8 {
+    (16..=255)
+        .min_by_key(|value| distance(rgb, ansi256_to_rgb(*value)))
+        .unwrap_or(16)
+}
+
+const fn rgb(hex: u32) -> Color {
+    let [_, r, g, b] = hex.to_be_bytes();
+    Color::Rgb { r, g, b }
+}
+
+#[derive(Clone, Debug)]
+pub struct Theme {
+    pub text: Style,
+    pub status_bar: Style,
+    pub message_bar: Style,
+    pub command_bar: Style,
+    pub gutter: Style,
+    pub empty_line: Style,
+    pub selection: Style,
+    pub search_match: Style,
+    pub selected_match: Style,
+    pub keyword: Style,
+    pub type_name: Style,
+    pub string: Style,
+    pub comment: Style,
+    pub number: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+/// Style of a scope as written in a theme file.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StyleSpec {
+    foreground: Option<String>,
+    background: Option<String>,
+    #[serde(default)]
+    attributes: Vec<String>,
+}
+
+/// A theme file overrides the scopes of its `base` theme, `dark` by default.
+#[derive(Deserialize)]
+struct ThemeFile {
+    base: Option<String>,
+    #[serde(flatten)]
+    scopes: BTreeMap<String, StyleSpec>,
+}
+
+impl Theme {
+    pub const fn dark() -> Self {
+        let text = Style::fg(0x00ab_b2bf).on(0x0028_2c34);
+        Self {
+            text,
+            status_bar: Style::fg(0x00ab_b2bf).on(0x003e_4451),
+            message_bar: text,
+            command_bar: text,
+            gutter: Style::fg(0x0063_6d83),
+            empty_line: Style::fg(0x003b_4048),
+            selection: Style::bg(0x003e_4451),
+            search_match: Style::fg(0x0028_2c34).on(0x00d1_9a66),
+            selected_match: Style::fg(0x0028_2c34).on(0x00e5_c07b).with(Attribute::Bold),
+            keyword: Style::fg(0x00c6_78dd),
+            type_name: Style::fg(0x00e5_c07b),
+            string: Style::fg(0x0098_c379),
+            comment: Style::fg(0x005c_6370).with(Attribute::Italic),
+            number: Style::fg(0x00d1_9a66),
+        }
+    }
+
+    pub const fn light() -> Self {
+        let text = Style::fg(0x0038_3a42).on(0x00fa_fafa);
+        Self {
+            text,
+            status_bar: Style::fg(0x0038_3a42).on(0x00e5_e5e6),
+            message_bar: text,
+            command_bar: text,
+            gutter: Style::fg(0x009d_9d9f),
+            empty_line: Style::fg(0x00d0_d0d0),
+            selection: Style::bg(0x00bf_ceff),
+            search_match: Style::bg(0x00f0_d58a),
+            selected_match: Style::bg(0x00e5_b84f).with(Attribute::Bold),
+            keyword: Style::fg(0x00a6_26a4),
+            type_name: Style::fg(0x00c1_8401),
+            string: Style::fg(0x0050_a14f),
+            comment: Style::fg(0x00a0_a1a7).with(Attribute::Italic),
+            number: Style::fg(0x0098_6801),
+        }
+    }
+
+    /// Returns the built-in theme called `name`, or loads the theme file at that path.
+    pub fn load(name: &str) -> Result<Self, String> {
+        match name {
+            "dark" => Ok(Self::dark()),
+            "light" => Ok(Self::light()),
+            path => {
+                let content = read_to_string(path)
+                    .map_err(|error| format!("Could not read theme {path}: {error}"))?;
+                Self::parse(&content).map_err(|error| format!("Invalid theme {path}: {error}"))
+            }
+        }
+    }
+
+    fn parse(content: &str) -> Result<Self, String> {
+        let file: ThemeFile =
+            toml::from_str(content).map_err(|error| error.message().to_string())?;
+        let mut theme = match file.base.as_deref() {
+            None | Some("dark") => Self::dark(),
+            Some("light") => Self::light(),
+            Some(base) => return Err(format!("unknown base theme `{base}`")),
+        };
+
+        for (name, spec) in file.scopes {
+            let style = theme
+                .scope_mut(&name)
+                .ok_or_else(|| format!("unknown scope `{name}`"))?;
+            *style = Style {
+                foreground: spec.foreground.as_deref().map(parse_color).transpose()?,
+                background: spec.background.as_deref().map(parse_color).transpose()?,
+                attributes: spec
+                    .attributes
+                    .iter()
+                    .map(|attribute| parse_attribute(attribute))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .into_iter()
+                    .fold(Attributes::none(), Attributes::with),
+            };
+        }
+        Ok(theme)
+    }
+
+    fn scope_mut(&mut self, name: &str) -> Option<&mut Style> {
+        match name {
+            "text" => Some(&mut self.text),
+            "status_bar" => Some(&mut self.status_bar),
+            "message_bar" => Some(&mut self.message_bar),
+            "command_bar" => Some(&mut self.command_bar),
+            "gutter" => Some(&mut self.gutter),
+            "empty_line" => Some(&mut self.empty_line),
+            "selection" => Some(&mut self.selection),
+            "match" => Some(&mut self.search_match),
+            "selected_match" => Some(&mut self.selected_match),
+            "keyword" => Some(&mut self.keyword),
+            "type" => Some(&mut self.type_name),
+            "string" => Some(&mut self.string),
+            "comment" => Some(&mut self.comment),
+            "number" => Some(&mut self.number),
+            _ => None,
+        }
+    }
+
+    pub fn style(&self, scope: Scope) -> Style {
+        match scope {
+            Scope::Text => self.text,
+            Scope::StatusBar => self.status_bar,
+            Scope::MessageBar => self.message_bar,
+            Scope::CommandBar => self.command_bar,
+            Scope::Gutter => self.gutter.over(self.text),
+            Scope::EmptyLine => self.empty_line.over(self.text),
+        }
+    }
+
+    /// Style of annotated text, drawn over the plain text style.
+    pub fn annotation_style(&self, annotation_type: AnnotationType) -> Style {
+        let style = match annotation_type {
+            AnnotationType::Selection => self.selection,
+            AnnotationType::Match => self.search_match,
+            AnnotationType::SelectedMatch => self.selected_match,
+            AnnotationType::Keyword => self.keyword,
+            AnnotationType::Type => self.type_name,
+            AnnotationType::String => self.string,
+            AnnotationType::Comment => self.comment,
+            AnnotationType::Number => self.number,
+        };
+        style.over(self.text)
+    }
+}
+
+/// Accepts `#rrggbb`, an ANSI color number or a color name such as `dark_blue`.
+fn parse_color(value: &str) -> Result<Color, String> {
+    let invalid = || format!("invalid color `{value}`");
+
+    if let Some(hex) = value.strip_prefix('#') {
+        if hex.len() != 6 {
+            return Err(invalid());
+        }
+        let [_, r, g, b] = u32::from_str_radix(hex, 16)
+            .map_err(|_| invalid())?
+            .to_be_bytes();
+        return Ok(Color::Rgb { r, g, b });
+    }
+    if let Ok(value) = value.parse::<u8>() {
+        return Ok(Color::AnsiValue(value));
+    }
+    Color::try_from(value).map_err(|()| invalid())
+}
+
+fn parse_attribute(value: &str) -> Result<Attribute, String> {
+    match value {
+        "bold" => Ok(Attribute::Bold),
+        "dim" => Ok(Attribute::Dim),
+        "italic" => Ok(Attribute::Italic),
+        "underline" => Ok(Attribute::Underlined),
+        "reverse" => Ok(Attribute::Reverse),
+        _ => Err(format!("unknown attribute `{value}`")),
+    }
+}
@@ -1,20 +1,21 @@
 use std::io::Error;
 use crate::size::Size;
+use crate::theme::Theme;
 
 pub trait UiComponent {
     fn mark_redraw(&mut self, val: bool);
     fn needs_redraw(&self) -> bool;
     fn set_size(&mut self, size: Size);
-    fn draw(&mut self, position_y: usize) -> Result<(), Error>;
+    fn draw(&mut self, position_y: usize, theme: &Theme) -> Result<(), Error>;
 
     fn resize(&mut self, size: Size) {
         self.set_size(size);
         self.mark_redraw(true);
     }
 
-    fn render(&mut self, position_y: usize) {
+    fn render(&mut self, position_y: usize, theme: &Theme) {
         if self.needs_redraw() {
-            match self.draw(position_y) {
+            match self.draw(position_y, theme) {
                 Ok(()) => self.mark_redraw(false),
                 Err(e) => {
                     #[cfg(debug_assertions)]
@@ -1,18 +1,34 @@
+use crate::annotation::Annotation;
+use crate::annotation_type::AnnotationType;
+use crate::backup::BackupPolicy;
 use crate::buffer::Buffer;
+use crate::disk_state::DiskChange;
 use crate::document_status::DocumentStatus;
 use crate::editor_commands::{Edit, Move};
-use crate::line::Line;
+use crate::goto_target::GotoTarget;
+use crate::highlighter::Highlighter;
+use crate::language::Language;
+use crate::line::{DEFAULT_TAB_WIDTH, Line};
+use crate::line_ending::LineEnding;
 use crate::location::Location;
 use crate::position::Position;
-use crate::serach_info::SearchInfo;
+use crate::register::Register;
+use crate::serach_info::{SearchInfo, SearchOptions};
 use crate::size::Size;
+use crate::swap_file::FoundSwap;
 use crate::terminal::Terminal;
+use crate::text_encoding::TextEncoding;
+use crate::theme::{Scope, Theme};
 use crate::ui_component::UiComponent;
+use regex::Regex;
 use std::cmp::min;
 use std::io::Error;
+use std::ops::Range;
+use std::path::Path;
 
 pub const NAME: &str = env!("CARGO_PKG_NAME");
 const VERSION: &str = env!("CARGO_PKG_VERSION");
+const MIN_LINE_NUMBER_WIDTH: usize = 4;
 
 #[derive(Default, Eq, PartialEq, Clone, Copy)]
 pub enum SearchDirection {
@@ -27,9 +43,15 @@ pub struct View {
     needs_redraw: bool,
     size: Size,
     text_location: Location,
+    selection_anchor: Option<Location>,
     scroll_offset: Position,
     show_line_numbers: bool,
+    /// Zero until `set_indentation` runs, read through `tab_width()`.
+    tab_width: usize,
+    soft_tabs: bool,
+    backup: BackupPolicy,
     search_info: Option<SearchInfo>,
+    highlighter: Highlighter,
 }
 
 impl UiComponent for View {
@@ -46,7 +68,7 @@ impl UiComponent for View {
         self.scroll_text_location_into_view();
     }
 
-    fn draw(&mut self, position_y: usize) -> Result<(), std::io::Error> {
+    fn draw(&mut self, position_y: usize, theme: &Theme) -> Result<(), std::io::Error> {
         let Size { height, width } = self.size;
         let end_y = position_y.saturating_add(height);
 
@@ -54,46 +76,51 @@ impl UiComponent for View {
         let top_third = height / 3;
         let scroll_top = self.scroll_offset.row;
 
-        let content_width = if self.show_line_numbers {
-            width.saturating_sub(6)
-        } else {
-            width
-        };
+        let gutter_width = self.gutter_width();
+        let content_width = width.saturating_sub(gutter_width);
+
+        self.highlighter
+            .update(&self.buffer.lines, scroll_top.saturating_add(height));
 
         for current_row in position_y..end_y {
             let line_idx = current_row
                 .saturating_sub(position_y)
                 .saturating_add(scroll_top);
 
-            Terminal::print_row(current_row, &" ".repeat(width))?;
+            Terminal::print_styled_row(current_row, &" ".repeat(width), theme.style(Scope::Text))?;
 
             if self.show_line_numbers {
+                let number_width = gutter_width.saturating_sub(2);
                 let line_number = if line_idx < self.buffer.number_of_lines() {
-                    format!("{:4}  ", line_idx + 1)
+                    format!("{:number_width$}  ", line_idx + 1)
                 } else {
-                    "     ".to_string()
+                    " ".repeat(gutter_width)
                 };
 
                 Terminal::move_caret(Position::new(current_row, 0))?;
-                Terminal::print(&line_number)?;
+                Terminal::print_styled(&line_number, theme.style(Scope::Gutter))?;
             }
 
-            let content_start = if self.show_line_numbers { 6 } else { 0 };
+            let content_start = gutter_width;
 
             if let Some(line) = self.buffer.lines.get(line_idx) {
                 let left = self.scroll_offset.col;
                 let right = self.scroll_offset.col.saturating_add(content_width);
-                let content = line.get_visible_graphemes(left..right);
+                let mut annotations = self.highlighter.annotations(line_idx).to_vec();
+                annotations.extend(self.search_annotations(line_idx, line));
+                annotations.extend(self.selection_annotations(line_idx, line));
+                let content =
+                    line.get_annotated_visible_substr(left..right, &annotations, self.tab_width());
 
                 Terminal::move_caret(Position::new(current_row, content_start))?;
-                Terminal::print(&content)?;
+                Terminal::print_annotated(&content, theme)?;
             } else if current_row == top_third && self.buffer.is_empty() {
                 let message = Self::build_welcome_message(content_width);
                 Terminal::move_caret(Position::new(current_row, content_start))?;
-                Terminal::print(&message)?;
+                Terminal::print_styled(&message, theme.style(Scope::Text))?;
             } else {
                 Terminal::move_caret(Position::new(current_row, content_start))?;
-                Terminal::print("~")?;
+                Terminal::print_styled("~", theme.style(Scope::EmptyLine))?;
             }
         }
         Ok(())
@@ -117,18 +144,53 @@ impl View {
             prev_location: self.text_location,
             prev_scroll_offset: self.scroll_offset,
             query: None,
+            pattern: None,
+            options: SearchOptions::default(),
+            match_starts: None,
         });
     }
 
     pub fn exit_search(&mut self) {
         self.search_info = None;
+        self.mark_redraw(true);
     }
 
-    pub fn search(&mut self, query: &str) {
+    /// Fails with a short description when the query is not a valid regex.
+    pub fn search(&mut self, query: &str) -> Result<(), String> {
         if let Some(search_info) = &mut self.search_info {
             search_info.query = Some(Line::from(query));
+            search_info.pattern = None;
+            search_info.match_starts = None;
+            self.needs_redraw = true;
+            search_info.pattern = Some(search_info.options.build_pattern(query)?);
         }
         self.search_in_direction(self.text_location, SearchDirection::default());
+        Ok(())
+    }
+
+    pub fn search_options(&self) -> SearchOptions {
+        self.search_info
+            .as_ref()
+            .map(|search_info| search_info.options)
+            .unwrap_or_default()
+    }
+
+    pub const fn toggle_search_regex(&mut self) {
+        if let Some(search_info) = &mut self.search_info {
+            search_info.options.regex = !search_info.options.regex;
+        }
+    }
+
+    pub const fn cycle_search_case_mode(&mut self) {
+        if let Some(search_info) = &mut self.search_info {
+            search_info.options.case_mode = search_info.options.case_mode.next();
+        }
+    }
+
+    pub const fn toggle_search_whole_word(&mut self) {
+        if let Some(search_info) = &mut self.search_info {
+            search_info.options.whole_word = !search_info.options.whole_word;
+        }
     }
 
     fn center_text_location(&mut self) {
@@ -159,15 +221,13 @@ impl View {
     }
 
     fn search_in_direction(&mut self, from: Location, direction: SearchDirection) {
-        if let Some(location) = self.get_search_query().and_then(|query| {
-            if query.is_empty() {
-                return None;
-            }
-            match direction {
-                SearchDirection::Forward => self.buffer.search_forward(from, query),
-                SearchDirection::Backward => self.buffer.search_backwards(from, query),
-            }
-        }) {
+        if let Some(location) = self
+            .get_search_pattern()
+            .and_then(|pattern| match direction {
+                SearchDirection::Forward => self.buffer.search_forward(from, pattern),
+                SearchDirection::Backward => self.buffer.search_backwards(from, pattern),
+            })
+        {
             self.text_location = location;
             self.center_text_location();
         };
@@ -187,31 +247,220 @@ impl View {
         query
     }
 
+    fn get_search_pattern(&self) -> Option<&Regex> {
+        self.search_info
+            .as_ref()
+            .and_then(|search_info| search_info.pattern.as_ref())
+            .filter(|_| {
+                self.get_search_query()
+                    .is_some_and(|query| !query.is_empty())
+            })
+    }
+
+    fn search_uses_regex(&self) -> bool {
+        self.search_info.as_ref().is_some_and(|search_info| {
+            search_info.query.as_ref().is_some_and(|query| {
+                let (_, regex) = search_info.options.parse_query(query);
+                regex
+            })
+        })
+    }
+
+    // === Replace === //
+
+    /// Replaces the match under the caret and moves on to the next one.
+    /// Returns `false` when there is no match under the caret.
+    pub fn replace_current(&mut self, replacement: &str) -> bool {
+        let expand = self.search_uses_regex();
+        let Some((end, text)) = self.get_search_pattern().and_then(|pattern| {
+            self.buffer
+                .lines
+                .get(self.text_location.line_index)
+                .and_then(|line| {
+                    line.replacement_at(
+                        self.text_location.grapheme_index,
+                        pattern,
+                        replacement,
+                        expand,
+                    )
+                })
+        }) else {
+            return false;
+        };
+
+        let start = self.text_location;
+        self.buffer.begin_undo_group();
+        self.buffer.delete_range(
+            start,
+            Location {
+                line_index: start.line_index,
+                grapheme_index: end,
+            },
+        );
+        let after = self.buffer.insert_str(start, &text);
+        self.buffer.end_undo_group();
+
+        self.text_location = after;
+        self.search_in_direction(after, SearchDirection::Forward);
+        self.scroll_text_location_into_view();
+        self.mark_redraw(true);
+        true
+    }
+
+    /// Replaces every match in the buffer as a single undoable step. Like searching, this
+    /// wraps around, so matches above the caret are replaced too.
+    pub fn replace_all(&mut self, replacement: &str) -> usize {
+        let expand = self.search_uses_regex();
+        let Some(pattern) = self.get_search_pattern().cloned() else {
+            return 0;
+        };
+
+        let mut replaced = 0;
+        self.buffer.begin_undo_group();
+        // Going backwards keeps the indices of the remaining lines valid when the
+        // replacement text spans several lines
+        for line_index in (0..self.buffer.number_of_lines()).rev() {
+            let Some(line) = self.buffer.lines.get(line_index) else {
+                continue;
+            };
+            let (text, count) = line.replace_matches_from(0, &pattern, replacement, expand);
+            if count == 0 {
+                continue;
+            }
+
+            let line_start = Location {
+                line_index,
+                grapheme_index: 0,
+            };
+            let line_end = Location {
+                line_index,
+                grapheme_index: line.grapheme_count(),
+            };
+            let line_count = self.buffer.number_of_lines();
+            self.buffer.delete_range(line_start, line_end);
+            self.buffer.insert_str(line_start, &text);
+            replaced += count;
+
+            // Keep the caret on its line when lines above it were split or joined
+            if line_index < self.text_location.line_index {
+                self.text_location.line_index = (self.text_location.line_index
+                    + self.buffer.number_of_lines())
+                .saturating_sub(line_count);
+            }
+        }
+        self.buffer.end_undo_group();
+
+        self.snap_to_valid_grapheme();
+        self.scroll_text_location_into_view();
+        self.mark_redraw(true);
+        replaced
+    }
+
+    /// Returns the index of the match under the caret, if any, and the number of matches in the buffer.
+    pub fn search_match_counts(&mut self) -> Option<(Option<usize>, usize)> {
+        let pattern = self.get_search_pattern()?.clone();
+        let revision = self.buffer.revision();
+        let search_info = self.search_info.as_mut()?;
+        if search_info
+            .match_starts
+            .as_ref()
+            .is_none_or(|(found_in, _)| *found_in != revision)
+        {
+            search_info.match_starts = Some((revision, self.buffer.match_starts(&pattern)));
+        }
+
+        let (_, starts) = search_info.match_starts.as_ref()?;
+        let current = starts.binary_search(&self.text_location).ok();
+        Some((current, starts.len()))
+    }
+
+    fn search_annotations(&self, line_idx: usize, line: &Line) -> Vec<Annotation> {
+        let Some(pattern) = self.get_search_pattern() else {
+            return Vec::new();
+        };
+
+        line.find_all(pattern)
+            .into_iter()
+            .map(|range| {
+                let is_current = line_idx == self.text_location.line_index
+                    && range.start == self.text_location.grapheme_index;
+                Annotation {
+                    kind: if is_current {
+                        AnnotationType::SelectedMatch
+                    } else {
+                        AnnotationType::Match
+                    },
+                    start: range.start,
+                    end: range.end,
+                }
+            })
+            .collect()
+    }
+
+    // === Go to line === //
+
+    pub fn goto(&mut self, target: GotoTarget) {
+        let current = self.text_location;
+        self.text_location = match target {
+            GotoTarget::Absolute { line, column } => Location {
+                line_index: line.saturating_sub(1),
+                grapheme_index: column.map_or(0, |column| column.saturating_sub(1)),
+            },
+            GotoTarget::Relative(offset) => Location {
+                line_index: current.line_index.saturating_add_signed(offset),
+                grapheme_index: current.grapheme_index,
+            },
+            GotoTarget::End => Location {
+                line_index: self.buffer.number_of_lines().saturating_sub(1),
+                grapheme_index: 0,
+            },
+        };
+        self.clear_selection();
+        self.snap_to_valid_line();
+        self.snap_to_valid_grapheme();
+        self.center_text_location();
+    }
+
     // === Command Handlers === //
 
     pub fn handle_edit_command(&mut self, command: Edit) {
         match command {
-            Edit::Insert(c) => self.insert_character(c),
+            Edit::Backspace | Edit::Delete if self.has_selection() => self.delete_selection(),
+            Edit::Insert('\t') if self.has_selection() => self.indent(),
+            Edit::Insert('\t') if self.soft_tabs => self.replace_selection(Self::insert_soft_tab),
+            Edit::Insert(c) => self.replace_selection(|view| view.insert_character(c)),
             Edit::Backspace => self.backspace(),
             Edit::Delete => self.delete(),
-            Edit::Enter => self.insert_newline(),
-            Edit::RemoveLine => self.delete_line(),
+            Edit::Enter => self.replace_selection(Self::insert_newline),
+            // Clipboard commands need the shared register, so they go through `copy`, `cut` and `paste`
+            Edit::Copy | Edit::Cut | Edit::Paste => {}
+            Edit::Undo => self.undo(),
+            Edit::Redo => self.redo(),
+            Edit::Indent => self.indent(),
+            Edit::Dedent => self.dedent(),
         }
     }
 
     pub fn handle_move_command(&mut self, command: Move) {
         let Size { height, .. } = self.size;
+        if command.is_selection() {
+            self.selection_anchor.get_or_insert(self.text_location);
+            self.mark_redraw(true);
+        } else {
+            self.clear_selection();
+        }
+
         match command {
-            Move::Up => self.move_up(1),
-            Move::Down => self.move_down(1),
-            Move::Left => self.move_left(),
-            Move::Right => self.move_right(),
-            Move::PageUp => self.move_up(height.saturating_sub(1)),
-            Move::PageDown => self.move_down(height.saturating_sub(1)),
-            Move::Home => self.move_to_beggining_of_line(),
-            Move::End => self.move_to_end_of_line(),
-            Move::WordJumpLeft => self.jump_word_left(),
-            Move::WordJumpRight => self.jump_word_right(),
+            Move::Up | Move::SelectUp => self.move_up(1),
+            Move::Down | Move::SelectDown => self.move_down(1),
+            Move::Left | Move::SelectLeft => self.move_left(),
+            Move::Right | Move::SelectRight => self.move_right(),
+            Move::PageUp | Move::SelectPageUp => self.move_up(height.saturating_sub(1)),
+            Move::PageDown | Move::SelectPageDown => self.move_down(height.saturating_sub(1)),
+            Move::Home | Move::SelectHome => self.move_to_beggining_of_line(),
+            Move::End | Move::SelectEnd => self.move_to_end_of_line(),
+            Move::WordJumpLeft | Move::SelectWordJumpLeft => self.jump_word_left(),
+            Move::WordJumpRight | Move::SelectWordJumpRight => self.jump_word_right(),
         }
         self.scroll_text_location_into_view();
     }
@@ -219,17 +468,20 @@ impl View {
     // === Saving Files === //
 
     pub(crate) fn save(&mut self) -> Result<(), Error> {
-        self.buffer.save()
+        self.buffer.save(&self.backup)
     }
 
     pub(crate) fn save_as(&mut self, file_name: &str) -> Result<(), Error> {
-        self.buffer.save_as(file_name)
+        self.buffer.save_as(file_name, &self.backup)?;
+        self.update_language();
+        Ok(())
     }
 
-    pub fn load(&mut self, file_name: &str) -> Result<(), Error> {
-        match Buffer::load(file_name) {
+    pub fn load(&mut self, file_name: &str, fallback: TextEncoding) -> Result<(), Error> {
+        match Buffer::load(file_name, fallback) {
             Ok(buffer) => {
                 self.buffer = buffer;
+                self.update_language();
                 self.mark_redraw(true);
                 Ok(())
             }
@@ -237,12 +489,122 @@ impl View {
         }
     }
 
+    /// Reads the file again, decoding it as `encoding`. Unsaved changes are replaced,
+    /// undo brings them back.
+    pub fn reload_with_encoding(&mut self, encoding: TextEncoding) -> Result<(), Error> {
+        self.buffer.reload(encoding)?;
+        self.selection_anchor = None;
+        self.snap_to_valid_line();
+        self.snap_to_valid_grapheme();
+        self.scroll_text_location_into_view();
+        self.mark_redraw(true);
+        Ok(())
+    }
+
+    /// Reads the file again in its current encoding.
+    pub fn reload(&mut self) -> Result<(), Error> {
+        self.reload_with_encoding(self.buffer.file_info.encoding)
+    }
+
+    pub fn check_disk(&mut self) -> DiskChange {
+        self.buffer.check_disk()
+    }
+
+    pub fn ignore_disk_change(&mut self) {
+        self.buffer.ignore_disk_change();
+    }
+
+    pub fn disk_diff(&self) -> Result<Option<String>, Error> {
+        self.buffer.disk_diff()
+    }
+
+    pub fn set_encoding(&mut self, encoding: TextEncoding) -> Result<(), Error> {
+        self.buffer.set_encoding(encoding)
+    }
+
+    // === Swap File === //
+
+    pub fn write_swap(&mut self) -> Result<(), Error> {
+        self.buffer.write_swap()
+    }
+
+    pub fn remove_swap(&mut self) {
+        self.buffer.remove_swap();
+    }
+
+    pub fn found_swap(&self) -> Option<&FoundSwap> {
+        self.buffer.found_swap()
+    }
+
+    /// True when the loaded file is the one at `path`, however either path is spelled.
+    pub fn is_editing(&self, path: &Path) -> bool {
+        self.buffer.file_info.path.as_deref().is_some_and(|own| {
+            own == path
+                || matches!(
+                    (own.canonicalize(), path.canonicalize()),
+                    (Ok(own), Ok(other)) if own == other
+                )
+        })
+    }
+
+    pub fn swap_path(&self) -> Option<&Path> {
+        self.buffer.swap_path()
+    }
+
+    pub fn found_swap_diff(&self) -> Result<Option<String>, Error> {
+        self.buffer.found_swap_diff()
+    }
+
+    pub fn recover_swap(&mut self) -> Result<(), Error> {
+        self.buffer.recover_swap()?;
+        self.selection_anchor = None;
+        self.snap_to_valid_line();
+        self.snap_to_valid_grapheme();
+        self.scroll_text_location_into_view();
+        self.mark_redraw(true);
+        Ok(())
+    }
+
+    pub fn discard_swap(&mut self) -> Result<(), Error> {
+        self.buffer.discard_swap()
+    }
+
+    /// Replaces the buffer by `text` that is not backed by a file.
+    pub fn show_text(&mut self, text: &str) {
+        self.buffer = Buffer::from_text(text);
+        self.text_location = Location::default();
+        self.selection_anchor = None;
+        self.scroll_offset = Position::default();
+        self.update_language();
+    }
+
+    fn update_language(&mut self) {
+        let language = self
+            .buffer
+            .file_info
+            .path
+            .as_deref()
+            .map_or(Language::PlainText, Language::from_path);
+        self.highlighter.set_language(language);
+        self.mark_redraw(true);
+    }
+
     pub const fn is_file_loaded(&self) -> bool {
         self.buffer.file_info.has_path()
     }
 
     // === Write text === //
 
+    /// Inserts spaces up to the next tab stop.
+    fn insert_soft_tab(&mut self) {
+        let column = self.text_location_to_position().col;
+        let tab_width = self.tab_width();
+        let spaces = " ".repeat(tab_width - column % tab_width);
+        self.text_location = self.buffer.insert_str(self.text_location, &spaces);
+        self.scroll_text_location_into_view();
+        self.mark_redraw(true);
+    }
+
     fn insert_character(&mut self, c: char) {
         let old_grapheme_len = self
             .buffer
@@ -267,22 +629,118 @@ impl View {
         self.mark_redraw(true);
     }
 
+    /// Starts a new line indented like the current one, one level deeper after an
+    /// opening bracket of the file type.
     fn insert_newline(&mut self) {
-        self.buffer.insert_newline(&self.text_location);
-        self.handle_move_command(Move::Right);
+        let indentation = self.new_line_indentation();
+        self.buffer.begin_undo_group();
+        self.remove_indentation_after_caret();
+        if indentation.is_empty() {
+            self.buffer.insert_newline(&self.text_location);
+            self.handle_move_command(Move::Right);
+        } else {
+            self.text_location = self
+                .buffer
+                .insert_str(self.text_location, &format!("\n{indentation}"));
+            self.scroll_text_location_into_view();
+        }
+        self.buffer.end_undo_group();
         self.mark_redraw(true);
     }
 
+    /// Inside the leading whitespace, drops the whitespace right of the caret, so the new
+    /// line is only indented by what is left of the caret.
+    fn remove_indentation_after_caret(&mut self) {
+        let Some(line) = self.buffer.lines.get(self.text_location.line_index) else {
+            return;
+        };
+        let indentation_end = line
+            .to_string()
+            .chars()
+            .take_while(|character| *character == ' ' || *character == '\t')
+            .count();
+        if self.text_location.grapheme_index < indentation_end {
+            let end = Location {
+                line_index: self.text_location.line_index,
+                grapheme_index: indentation_end,
+            };
+            self.buffer.delete_range(self.text_location, end);
+        }
+    }
+
+    fn new_line_indentation(&self) -> String {
+        let Some(line) = self.buffer.lines.get(self.text_location.line_index) else {
+            return String::new();
+        };
+        let before_caret = line.get_substring(0..self.text_location.grapheme_index);
+        let mut indentation: String = before_caret
+            .chars()
+            .take_while(|character| *character == ' ' || *character == '\t')
+            .collect();
+
+        let opens_block = self.highlighter.language().syntax().is_some_and(|syntax| {
+            before_caret
+                .trim_end()
+                .ends_with(|last| syntax.indent_after.contains(&last))
+        });
+        if opens_block {
+            indentation.push_str(&self.indent_unit());
+        }
+        indentation
+    }
+
+    /// One level of indentation, following the soft tabs setting.
+    fn indent_unit(&self) -> String {
+        if self.soft_tabs {
+            " ".repeat(self.tab_width())
+        } else {
+            String::from("\t")
+        }
+    }
+
     // === Delete text === //
 
     fn backspace(&mut self) {
         if self.text_location.grapheme_index == 0 && self.text_location.line_index == 0 {
             return;
         }
+        if self.soft_tabs && self.remove_indent_level() {
+            return;
+        }
         self.handle_move_command(Move::Left);
         self.delete();
     }
 
+    /// When only spaces precede the caret, removes them back to the previous tab stop.
+    fn remove_indent_level(&mut self) -> bool {
+        let Location {
+            line_index,
+            grapheme_index,
+        } = self.text_location;
+        let Some(line) = self.buffer.lines.get(line_index) else {
+            return false;
+        };
+        if grapheme_index == 0
+            || !line
+                .get_substring(0..grapheme_index)
+                .chars()
+                .all(|character| character == ' ')
+        {
+            return false;
+        }
+
+        let removed = (grapheme_index - 1) % self.tab_width() + 1;
+        let start = Location {
+            line_index,
+            grapheme_index: grapheme_index - removed,
+        };
+        self.buffer.delete_range(start, self.text_location);
+        self.text_location = start;
+        self.scroll_text_location_into_view();
+        self.mark_redraw(true);
+        true
+    }
+
     fn delete(&mut self) {
         self.buffer.delete(&self.text_location);
         self.mark_redraw(true);
@@ -294,6 +752,238 @@ impl View {
         self.mark_redraw(true);
     }
 
+    // === Indentation === //
+
+    /// Indices of the lines touched by the selection, or of the caret line without one.
+    fn selected_lines(&self) -> Range<usize> {
+        let (start, end) = self
+            .selection_range()
+            .unwrap_or((self.text_location, self.text_location));
+        let last = if end.grapheme_index == 0 && end.line_index > start.line_index {
+            end.line_index - 1
+        } else {
+            end.line_index
+        };
+        start.line_index..min(last.saturating_add(1), self.buffer.number_of_lines())
+    }
+
+    /// Adds one indent unit in front of every selected line that is not empty,
+    /// as a single undoable step.
+    fn indent(&mut self) {
+        let unit = self.indent_unit();
+        let unit_len = unit.chars().count();
+
+        self.buffer.begin_undo_group();
+        for line_index in self.selected_lines() {
+            if self.buffer.lines[line_index].is_empty() {
+                continue;
+            }
+            let at = Location {
+                line_index,
+                grapheme_index: 0,
+            };
+            self.buffer.insert_str(at, &unit);
+            self.shift_locations_on_line(line_index, |index| index.saturating_add(unit_len));
+        }
+        self.buffer.end_undo_group();
+        self.scroll_text_location_into_view();
+        self.mark_redraw(true);
+    }
+
+    /// Removes one level of indentation from every selected line, as a single undoable step.
+    fn dedent(&mut self) {
+        self.buffer.begin_undo_group();
+        for line_index in self.selected_lines() {
+            let removed = self.first_indent_level_len(&self.buffer.lines[line_index]);
+            if removed == 0 {
+                continue;
+            }
+            self.buffer.delete_range(
+                Location {
+                    line_index,
+                    grapheme_index: 0,
+                },
+                Location {
+                    line_index,
+                    grapheme_index: removed,
+                },
+            );
+            self.shift_locations_on_line(line_index, |index| index.saturating_sub(removed));
+        }
+        self.buffer.end_undo_group();
+        self.scroll_text_location_into_view();
+        self.mark_redraw(true);
+    }
+
+    /// Number of graphemes making up the first level of indentation of `line`:
+    /// a tab, or up to a tab width of spaces.
+    fn first_indent_level_len(&self, line: &Line) -> usize {
+        if line.starts_with('\t') {
+            1
+        } else {
+            line.chars()
+                .take(self.tab_width())
+                .take_while(|character| *character == ' ')
+                .count()
+        }
+    }
+
+    /// Keeps the caret and the selection anchor on the same text when a line is re-indented.
+    fn shift_locations_on_line(&mut self, line_index: usize, shift: impl Fn(usize) -> usize) {
+        for location in std::iter::once(&mut self.text_location).chain(&mut self.selection_anchor) {
+            if location.line_index == line_index {
+                location.grapheme_index = shift(location.grapheme_index);
+            }
+        }
+    }
+
+    // === Selection === //
+
+    fn selection_range(&self) -> Option<(Location, Location)> {
+        let anchor = self.selection_anchor?;
+        let caret = self.text_location;
+        match anchor.cmp(&caret) {
+            std::cmp::Ordering::Less => Some((anchor, caret)),
+            std::cmp::Ordering::Greater => Some((caret, anchor)),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    fn has_selection(&self) -> bool {
+        self.selection_range().is_some()
+    }
+
+    fn clear_selection(&mut self) {
+        if self.selection_anchor.take().is_some() {
+            self.mark_redraw(true);
+        }
+    }
+
+    fn delete_selection(&mut self) {
+        if let Some((start, end)) = self.selection_range() {
+            self.buffer.delete_range(start, end);
+            self.selection_anchor = None;
+            self.text_location = start;
+            self.scroll_text_location_into_view();
+            self.mark_redraw(true);
+        }
+    }
+
+    /// Runs `edit` in place of the selected text, as a single undoable step.
+    fn replace_selection(&mut self, edit: impl FnOnce(&mut Self)) {
+        if !self.has_selection() {
+            self.clear_selection();
+            edit(self);
+            return;
+        }
+        self.buffer.begin_undo_group();
+        self.delete_selection();
+        edit(self);
+        self.buffer.end_undo_group();
+    }
+
+    fn selection_annotations(&self, line_idx: usize, line: &Line) -> Vec<Annotation> {
+        let Some((start, end)) = self.selection_range() else {
+            return Vec::new();
+        };
+        if line_idx < start.line_index || line_idx > end.line_index {
+            return Vec::new();
+        }
+
+        vec![Annotation {
+            kind: AnnotationType::Selection,
+            start: if line_idx == start.line_index {
+                start.grapheme_index
+            } else {
+                0
+            },
+            end: if line_idx == end.line_index {
+                end.grapheme_index
+            } else {
+                line.grapheme_count()
+            },
+        }]
+    }
+
+    // === Clipboard === //
+
+    pub fn copy(&self) -> Option<Register> {
+        if let Some((start, end)) = self.selection_range() {
+            return Some(Register {
+                text: self.buffer.text_in_range(start, end),
+                linewise: false,
+            });
+        }
+
+        self.buffer
+            .lines
+            .get(self.text_location.line_index)
+            .map(|line| Register {
+                text: line.to_string(),
+                linewise: true,
+            })
+    }
+
+    pub fn cut(&mut self) -> Option<Register> {
+        let register = self.copy()?;
+        if self.has_selection() {
+            self.delete_selection();
+        } else {
+            self.delete_line();
+        }
+        Some(register)
+    }
+
+    pub fn paste(&mut self, register: &Register) {
+        if register.linewise && !self.has_selection() {
+            self.clear_selection();
+            let line_index = self.text_location.line_index;
+            let at = Location {
+                line_index,
+                grapheme_index: 0,
+            };
+            if line_index < self.buffer.number_of_lines() {
+                self.buffer.insert_str(at, &format!("{}\n", register.text));
+            } else {
+                self.buffer.insert_str(at, &register.text);
+            }
+            self.text_location = Location {
+                line_index: line_index.saturating_add(1),
+                grapheme_index: 0,
+            };
+            self.snap_to_valid_line();
+        } else {
+            self.replace_selection(|view| {
+                view.text_location = view.buffer.insert_str(view.text_location, &register.text);
+            });
+        }
+        self.scroll_text_location_into_view();
+        self.mark_redraw(true);
+    }
+
+    // === Undo / Redo === //
+
+    fn undo(&mut self) {
+        if let Some(location) = self.buffer.undo() {
+            self.restore_text_location(location);
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(location) = self.buffer.redo() {
+            self.restore_text_location(location);
+        }
+    }
+
+    fn restore_text_location(&mut self, location: Location) {
+        self.selection_anchor = None;
+        self.text_location = location;
+        self.snap_to_valid_line();
+        self.snap_to_valid_grapheme();
+        self.scroll_text_location_into_view();
+        self.mark_redraw(true);
+    }
+
     // === Movement functions === //
 
     fn move_up(&mut self, step: usize) {
@@ -459,7 +1149,7 @@ impl View {
     // === Scroll === //
 
     fn scroll_horizontally(&mut self, to: usize) {
-        let Size { width, .. } = self.size;
+        let width = self.size.width.saturating_sub(self.gutter_width());
         let offset_changed = if to < self.scroll_offset.col {
             self.scroll_offset.col = to;
             true
@@ -517,6 +1207,48 @@ impl View {
 
     // === Other === //
 
+    pub fn set_line_numbers(&mut self, show: bool) {
+        self.show_line_numbers = show;
+        self.mark_redraw(true);
+    }
+
+    pub fn set_indentation(&mut self, tab_width: usize, soft_tabs: bool) {
+        self.tab_width = tab_width.max(1);
+        self.soft_tabs = soft_tabs;
+        self.mark_redraw(true);
+    }
+
+    const fn tab_width(&self) -> usize {
+        if self.tab_width == 0 {
+            DEFAULT_TAB_WIDTH
+        } else {
+            self.tab_width
+        }
+    }
+
+    /// Columns taken by the line numbers, wide enough for the last line number.
+    fn gutter_width(&self) -> usize {
+        if self.show_line_numbers {
+            let digits = self.buffer.number_of_lines().max(1).ilog10() as usize + 1;
+            digits.max(MIN_LINE_NUMBER_WIDTH).saturating_add(2)
+        } else {
+            0
+        }
+    }
+
+    pub fn set_backup(&mut self, backup: BackupPolicy) {
+        self.backup = backup;
+    }
+
+    /// Converts the file between LF and CRLF line endings, returning the new style.
+    pub fn toggle_line_ending(&mut self) -> LineEnding {
+        let line_ending = self.buffer.file_info.line_ending.toggled();
+        self.buffer.set_line_ending(line_ending);
+        self.snap_to_valid_grapheme();
+        self.mark_redraw(true);
+        line_ending
+    }
+
     pub fn toggle_line_numbers(&mut self) {
         let show = self.show_line_numbers;
         self.show_line_numbers = !show;
@@ -542,7 +1274,7 @@ impl View {
     fn text_location_to_position(&self) -> Position {
         let row = self.text_location.line_index;
         let col = self.buffer.lines.get(row).map_or(0, |line| {
-            line.width_until(self.text_location.grapheme_index)
+            line.width_until(self.text_location.grapheme_index, self.tab_width())
         });
 
         Position { row, col }
@@ -552,9 +1284,7 @@ impl View {
         let mut position = self
             .text_location_to_position()
             .saturating_sub(self.scroll_offset);
-        if self.show_line_numbers {
-            position.col = position.col.saturating_add(6);
-        }
+        position.col = position.col.saturating_add(self.gutter_width());
         position
     }
 
@@ -564,6 +1294,63 @@ impl View {
             number_of_lines: self.buffer.number_of_lines(),
             line_number: self.text_location.line_index,
             modified: self.buffer.dirty,
+            file_type: self.highlighter.language().to_string(),
+            file_format: self.buffer.file_info.format_to_string(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view_with(text: &str, at: Location) -> View {
+        let mut view = View::default();
+        view.buffer.insert_str(Location::default(), text);
+        view.text_location = at;
+        view
+    }
+
+    fn text(view: &View) -> Vec<String> {
+        view.buffer.lines.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn enter_keeps_the_indentation() {
+        let mut view = view_with(
+            "    foo",
+            Location {
+                line_index: 0,
+                grapheme_index: 7,
+            },
+        );
+        view.handle_edit_command(Edit::Enter);
+
+        assert_eq!(text(&view), ["    foo", "    "]);
+        assert_eq!(view.text_location.grapheme_index, 4);
+    }
+
+    #[test]
+    fn enter_inside_the_indentation_only_keeps_what_is_left_of_the_caret() {
+        let mut view = view_with(
+            "    foo",
+            Location {
+                line_index: 0,
+                grapheme_index: 2,
+            },
+        );
+        view.handle_edit_command(Edit::Enter);
+
+        assert_eq!(text(&view), ["  ", "  foo"]);
+        assert_eq!(
+            view.text_location,
+            Location {
+                line_index: 1,
+                grapheme_index: 2
+            }
+        );
+
+        view.handle_edit_command(Edit::Undo);
+        assert_eq!(text(&view), ["    foo"]);
+    }
+}